@@ -88,7 +88,7 @@ fn run_backtrack_limit(c: &mut Criterion) {
     let p = compile(&a).unwrap();
     let s = "abababababababababababababababababababababababababababab";
     c.bench_function("run_backtrack_limit", |b| {
-        b.iter(|| run_default(&p, &s, 0).unwrap_err())
+        b.iter(|| run_default(&p, s, 0).unwrap_err())
     });
 }
 
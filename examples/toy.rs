@@ -62,7 +62,7 @@ fn main() {
                         print!("_");
                     }
                 }
-                println!("");
+                println!();
                 for cap in caps.iter() {
                     println!("iterate {:?}", cap);
                 }
@@ -21,11 +21,17 @@
 //! Analysis of regex expressions.
 
 use bit_set::BitSet;
+use regex_syntax::hir::{Class, ClassUnicode, ClassUnicodeRange, Hir, HirKind, Literal};
+use regex_syntax::ParserBuilder;
 use std::cmp::min;
-use std::usize;
+use std::collections::HashMap;
+use std::iter;
+use std::mem;
 
+use crate::vm::Anchor;
 use crate::Error;
 use crate::Expr;
+use crate::LookAround::*;
 use crate::Result;
 
 #[derive(Debug)]
@@ -55,6 +61,190 @@ impl<'a> Info<'a> {
         }
     }
 
+    /// Like `is_literal`, but for literals that are matched ignoring case. Returns whether Unicode
+    /// case folding is used (which has to be the same for all parts of the literal).
+    pub(crate) fn casei_literal(&self) -> Option<bool> {
+        match *self.expr {
+            Expr::Literal {
+                casei: true,
+                unicode,
+                ..
+            } => Some(unicode),
+            Expr::Concat(_) => {
+                let (first, rest) = self.children.split_first()?;
+                let unicode = first.casei_literal()?;
+                rest.iter()
+                    .all(|child| child.casei_literal() == Some(unicode))
+                    .then_some(unicode)
+            }
+            _ => None,
+        }
+    }
+
+    /// Collect the names of all named groups in the expression, with the indexes of the groups
+    /// that have the name (in the order they are defined).
+    pub(crate) fn collect_group_names(&self, names: &mut HashMap<String, Vec<usize>>) {
+        if let Expr::NamedGroup { ref name, .. } = *self.expr {
+            let groups = names.entry(name.clone()).or_default();
+            // with branch reset, groups in different alternatives can have the same index
+            if !groups.contains(&self.start_group) {
+                groups.push(self.start_group);
+            }
+        }
+        for child in &self.children {
+            child.collect_group_names(names);
+        }
+    }
+
+    /// Returns the longest literal that every match of the expression has to contain (possibly
+    /// in a look-around), if there is one. If a text doesn't contain it, there can't be a match.
+    /// If `look_behind` is false, literals in look-behinds are not considered, so the literal has
+    /// to be after the start of the match.
+    pub(crate) fn required_literal(&self, look_behind: bool) -> Option<String> {
+        match *self.expr {
+            Expr::Literal { ref val, casei, .. } if !casei && !val.is_empty() => Some(val.clone()),
+            Expr::Concat(_) => {
+                let mut longest: Option<String> = None;
+                let mut keep_longest = |literal: String| {
+                    if literal.len() > longest.as_ref().map_or(0, |l| l.len()) {
+                        longest = Some(literal);
+                    }
+                };
+                // adjacent literals form a longer literal
+                let mut adjacent = String::new();
+                for child in &self.children {
+                    if child.is_literal() {
+                        child.push_literal(&mut adjacent);
+                        continue;
+                    }
+                    keep_longest(std::mem::take(&mut adjacent));
+                    if let Some(literal) = child.required_literal(look_behind) {
+                        keep_longest(literal);
+                    }
+                }
+                keep_longest(adjacent);
+                longest
+            }
+            Expr::Group(_) | Expr::NamedGroup { .. } | Expr::AtomicGroup(_) => {
+                self.children[0].required_literal(look_behind)
+            }
+            Expr::LookAround(_, LookAhead) => self.children[0].required_literal(look_behind),
+            Expr::LookAround(_, LookBehind) if look_behind => {
+                self.children[0].required_literal(look_behind)
+            }
+            Expr::Repeat { lo, .. } if lo > 0 => self.children[0].required_literal(look_behind),
+            _ => None,
+        }
+    }
+
+    /// Add the bytes that a match of the expression can start with to `bytes`. Returns whether the
+    /// expression can also match without consuming anything, so that what follows it can start
+    /// the match as well, or `None` if the bytes are not known.
+    pub(crate) fn first_bytes(&self, bytes: &mut [bool; 256]) -> Option<bool> {
+        match *self.expr {
+            Expr::Empty
+            | Expr::StartText
+            | Expr::StartLine
+            | Expr::EndText
+            | Expr::EndLine
+            | Expr::LookAround(..)
+            | Expr::KeepOut
+            | Expr::ContinueFromPreviousMatchEnd
+            | Expr::Delegate { size: 0, .. } => Some(true),
+            Expr::Literal {
+                ref val,
+                casei,
+                unicode,
+            } => match val.chars().next() {
+                Some(c) => {
+                    let mut class = ClassUnicode::new(iter::once(ClassUnicodeRange::new(c, c)));
+                    if casei && unicode {
+                        class.case_fold_simple();
+                    } else if casei && c.is_ascii_alphabetic() {
+                        // without Unicode mode, only the case of ASCII letters is ignored
+                        let other = if c.is_ascii_lowercase() {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c.to_ascii_lowercase()
+                        };
+                        class.push(ClassUnicodeRange::new(other, other));
+                    }
+                    add_first_bytes(&class, bytes);
+                    Some(false)
+                }
+                None => Some(true),
+            },
+            Expr::Delegate {
+                ref inner, casei, ..
+            } => {
+                match *parse_delegate(inner, casei)?.kind() {
+                    HirKind::Class(Class::Unicode(ref class)) => add_first_bytes(class, bytes),
+                    HirKind::Class(Class::Bytes(ref class)) => {
+                        for range in class.iter() {
+                            for b in range.start()..=range.end() {
+                                bytes[b as usize] = true;
+                            }
+                        }
+                    }
+                    HirKind::Literal(Literal(ref literal)) => bytes[literal[0] as usize] = true,
+                    _ => return None,
+                }
+                Some(false)
+            }
+            Expr::Concat(_) => {
+                for child in &self.children {
+                    if !child.first_bytes(bytes)? {
+                        return Some(false);
+                    }
+                }
+                Some(true)
+            }
+            Expr::Alt(_) | Expr::BranchReset(_) => {
+                let mut can_be_empty = false;
+                for child in &self.children {
+                    can_be_empty |= child.first_bytes(bytes)?;
+                }
+                Some(can_be_empty)
+            }
+            Expr::Group(_) | Expr::NamedGroup { .. } | Expr::AtomicGroup(_) => {
+                self.children[0].first_bytes(bytes)
+            }
+            Expr::Repeat { hi: 0, .. } => Some(true),
+            Expr::Repeat { lo, .. } => {
+                let can_be_empty = self.children[0].first_bytes(bytes)?;
+                Some(can_be_empty || lo == 0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns where a match of the expression can start if every match has to start with `^` or
+    /// `\A` (`StartText`), or with `^` in multi-line mode (`StartLine`).
+    pub(crate) fn anchored_start(&self) -> Option<Anchor> {
+        match *self.expr {
+            Expr::StartText => Some(Anchor::StartText),
+            Expr::Delegate { ref inner, .. } if matches!(inner.as_str(), "\\A" | "(?-u:\\A)") => {
+                Some(Anchor::StartText)
+            }
+            Expr::StartLine => Some(Anchor::StartLine),
+            Expr::Concat(_) => self.children.first()?.anchored_start(),
+            Expr::Alt(_) | Expr::BranchReset(_) => {
+                let mut anchor = Anchor::StartText;
+                for child in &self.children {
+                    if child.anchored_start()? == Anchor::StartLine {
+                        anchor = Anchor::StartLine;
+                    }
+                }
+                Some(anchor)
+            }
+            Expr::Group(_) | Expr::NamedGroup { .. } | Expr::AtomicGroup(_) => {
+                self.children[0].anchored_start()
+            }
+            Expr::Repeat { lo, .. } if lo > 0 => self.children[0].anchored_start(),
+            _ => None,
+        }
+    }
+
     pub(crate) fn push_literal(&self, buf: &mut String) {
         match *self.expr {
             // could be more paranoid about checking casei
@@ -69,16 +259,36 @@ impl<'a> Info<'a> {
     }
 }
 
+/// Parse the regex of a delegate, allowing it to match bytes that are not valid UTF-8.
+pub(crate) fn parse_delegate(inner: &str, casei: bool) -> Option<Hir> {
+    let inner = if casei {
+        format!("(?i:{})", inner)
+    } else {
+        inner.to_string()
+    };
+    let mut parser = ParserBuilder::new().utf8(false).build();
+    parser.parse(&inner).ok()
+}
+
 struct Analyzer<'a> {
     backrefs: &'a BitSet,
     group_ix: usize,
+    /// The index of the first group with each name
+    group_names: HashMap<&'a str, usize>,
+    /// Whether the expression contains subroutine calls
+    has_calls: bool,
+    /// The minimum size of each group (by group number) to use for subroutine calls, `usize::MAX`
+    /// if not known (yet)
+    call_min_sizes: Vec<usize>,
+    /// The minimum size of each group as determined by the current visit
+    group_min_sizes: Vec<usize>,
 }
 
 impl<'a> Analyzer<'a> {
     fn visit(&mut self, expr: &'a Expr) -> Result<Info<'a>> {
         let start_group = self.group_ix;
         let mut children = Vec::new();
-        let mut min_size = 0;
+        let mut min_size: usize = 0;
         let mut const_size = false;
         let mut hard = false;
         let mut looks_left = false;
@@ -90,7 +300,7 @@ impl<'a> Analyzer<'a> {
                 min_size = 1;
                 const_size = true;
             }
-            Expr::Literal { ref val, casei } => {
+            Expr::Literal { ref val, casei, .. } => {
                 // right now each character in a literal gets its own node, that might change
                 min_size = 1;
                 const_size = literal_const_size(val, casei);
@@ -99,12 +309,16 @@ impl<'a> Analyzer<'a> {
                 const_size = true;
                 looks_left = true;
             }
+            Expr::ContinueFromPreviousMatchEnd | Expr::KeepOut => {
+                const_size = true;
+                hard = true;
+            }
             Expr::Concat(ref v) => {
                 const_size = true;
                 for child in v {
                     let child_info = self.visit(child)?;
                     looks_left |= child_info.looks_left && min_size == 0;
-                    min_size += child_info.min_size;
+                    min_size = min_size.saturating_add(child_info.min_size);
                     const_size &= child_info.const_size;
                     hard |= child_info.hard;
                     children.push(child_info);
@@ -126,7 +340,29 @@ impl<'a> Analyzer<'a> {
                     children.push(child_info);
                 }
             }
-            Expr::Group(ref child) => {
+            Expr::BranchReset(ref v) => {
+                let mut end_group = start_group;
+                for (i, child) in v.iter().enumerate() {
+                    // the groups of each alternative start with the same index
+                    self.group_ix = start_group;
+                    let child_info = self.visit(child)?;
+                    end_group = end_group.max(self.group_ix);
+                    if i == 0 {
+                        min_size = child_info.min_size;
+                        const_size = child_info.const_size;
+                    } else {
+                        const_size &= child_info.const_size && min_size == child_info.min_size;
+                        min_size = min(min_size, child_info.min_size);
+                    }
+                    hard |= child_info.hard;
+                    looks_left |= child_info.looks_left;
+                    children.push(child_info);
+                }
+                self.group_ix = end_group;
+                // The regex crate would number the groups differently
+                hard |= end_group > start_group;
+            }
+            Expr::Group(ref child) | Expr::NamedGroup { ref child, .. } => {
                 let group = self.group_ix;
                 self.group_ix += 1;
                 let child_info = self.visit(child)?;
@@ -137,6 +373,15 @@ impl<'a> Analyzer<'a> {
                 // group. E.g. with `(x|xy)\1` and input `xyxy`, `x` matches but then the backref
                 // doesn't, so we have to backtrack and try `xy`.
                 hard = child_info.hard | self.backrefs.contains(group);
+                if let Expr::NamedGroup { ref name, .. } = *expr {
+                    // The regex crate doesn't allow multiple groups with the same name, so only the
+                    // first one can be delegated
+                    hard |= *self.group_names.entry(name).or_insert(group) != group;
+                }
+                if self.group_min_sizes.len() <= group {
+                    self.group_min_sizes.resize(group + 1, usize::MAX);
+                }
+                self.group_min_sizes[group] = min_size;
                 children.push(child_info);
             }
             Expr::LookAround(ref child, _) => {
@@ -151,7 +396,7 @@ impl<'a> Analyzer<'a> {
                 ref child, lo, hi, ..
             } => {
                 let child_info = self.visit(child)?;
-                min_size = child_info.min_size * lo;
+                min_size = child_info.min_size.saturating_mul(lo);
                 const_size = child_info.const_size && lo == hi;
                 hard = child_info.hard;
                 looks_left = child_info.looks_left;
@@ -163,12 +408,18 @@ impl<'a> Analyzer<'a> {
                 const_size = true;
                 looks_left = size == 0; // TODO: conservative for \z
             }
-            Expr::Backref(group) => {
+            Expr::Backref { group, .. } => {
                 if group >= self.group_ix {
                     return Err(Error::InvalidBackref);
                 }
                 hard = true;
             }
+            Expr::MultiBackref { ref groups, .. } => {
+                if groups.iter().any(|&group| group >= self.group_ix) {
+                    return Err(Error::InvalidBackref);
+                }
+                hard = true;
+            }
             Expr::AtomicGroup(ref child) => {
                 let child_info = self.visit(child)?;
                 min_size = child_info.min_size;
@@ -177,6 +428,66 @@ impl<'a> Analyzer<'a> {
                 hard = true; // TODO: possibly could weaken
                 children.push(child_info);
             }
+            Expr::BackrefExistsCondition(group) => {
+                if group >= self.group_ix {
+                    return Err(Error::InvalidBackref);
+                }
+                const_size = true;
+                hard = true;
+            }
+            Expr::SubroutineCall(group) => {
+                self.has_calls = true;
+                min_size = self
+                    .call_min_sizes
+                    .get(group)
+                    .copied()
+                    .unwrap_or(usize::MAX);
+                hard = true;
+            }
+            Expr::Absent {
+                ref absent,
+                ref child,
+            } => {
+                let absent_info = self.visit(absent)?;
+                let child_info = self.visit(child)?;
+                min_size = child_info.min_size;
+                const_size = child_info.const_size;
+                hard = true;
+                children.push(absent_info);
+                children.push(child_info);
+            }
+            Expr::AbsentStopper(ref absent) => {
+                children.push(self.visit(absent)?);
+                const_size = true;
+                hard = true;
+            }
+            Expr::AbsentRangeClear => {
+                const_size = true;
+                hard = true;
+            }
+            Expr::Conditional {
+                ref condition,
+                ref true_branch,
+                ref false_branch,
+            } => {
+                let condition_info = self.visit(condition)?;
+                let true_info = self.visit(true_branch)?;
+                let false_info = self.visit(false_branch)?;
+                // The true branch matches after the condition, the false branch instead of it
+                let true_size = condition_info.min_size.saturating_add(true_info.min_size);
+                min_size = min(true_size, false_info.min_size);
+                const_size = condition_info.const_size
+                    && true_info.const_size
+                    && false_info.const_size
+                    && true_size == false_info.min_size;
+                looks_left = condition_info.looks_left
+                    || (condition_info.min_size == 0 && true_info.looks_left)
+                    || false_info.looks_left;
+                hard = true;
+                children.push(condition_info);
+                children.push(true_info);
+                children.push(false_info);
+            }
         };
 
         Ok(Info {
@@ -192,10 +503,24 @@ impl<'a> Analyzer<'a> {
     }
 }
 
+/// Add the first bytes of the UTF-8 encoding of the chars in the class to `bytes`
+fn add_first_bytes(class: &ClassUnicode, bytes: &mut [bool; 256]) {
+    let first_byte = |c: char| c.encode_utf8(&mut [0; 4]).as_bytes()[0];
+    for range in class.iter() {
+        // The first byte increases with the char, but continuation bytes can't be first bytes
+        for b in first_byte(range.start())..=first_byte(range.end()) {
+            if !(0x80..0xC0).contains(&b) {
+                bytes[b as usize] = true;
+            }
+        }
+    }
+}
+
+/// Whether the literal always matches the same number of chars. That's the case even when
+/// ignoring case, because simple case folding (which is used by both regex and `Insn::LitCasei`)
+/// only maps single chars to single chars, e.g. `ß` matches `ẞ` but not `SS`. The number of bytes
+/// can differ though (e.g. `k` and `\u{212A}` KELVIN SIGN), so sizes are always counted in chars.
 fn literal_const_size(_: &str, _: bool) -> bool {
-    // Right now, regex doesn't do sophisticated case folding,
-    // test below will fail when that changes, then we need to
-    // do something fancier here.
     true
 }
 
@@ -204,17 +529,113 @@ pub fn analyze<'a>(expr: &'a Expr, backrefs: &'a BitSet) -> Result<Info<'a>> {
     let mut analyzer = Analyzer {
         backrefs,
         group_ix: 0,
+        group_names: HashMap::new(),
+        has_calls: false,
+        call_min_sizes: Vec::new(),
+        group_min_sizes: Vec::new(),
     };
 
-    analyzer.visit(expr)
+    let mut info = analyzer.visit(expr)?;
+    if analyzer.has_calls {
+        // The minimum size of a subroutine call is the one of the called group, which can contain
+        // calls itself (recursion). Start with unknown sizes and visit again until they're stable.
+        while analyzer.group_min_sizes != analyzer.call_min_sizes {
+            analyzer.call_min_sizes = mem::take(&mut analyzer.group_min_sizes);
+            analyzer.group_ix = 0;
+            info = analyzer.visit(expr)?;
+        }
+        check_left_recursion(&info)?;
+    }
+    Ok(info)
+}
+
+/// Check that no group can call itself (directly or through other groups) without matching
+/// anything first, e.g. `(?<n>a|\g<n>b)`, because that would recurse infinitely.
+fn check_left_recursion(info: &Info<'_>) -> Result<()> {
+    // For each group, the groups that it calls before matching anything
+    let mut left_calls = vec![BitSet::new(); info.end_group];
+    collect_left_calls(info, &[], &mut left_calls);
+
+    // Find a cycle with a depth-first search
+    fn visit(
+        group: usize,
+        left_calls: &[BitSet],
+        visiting: &mut BitSet,
+        done: &mut BitSet,
+    ) -> bool {
+        if done.contains(group) {
+            return false;
+        }
+        if !visiting.insert(group) {
+            return true;
+        }
+        let cycle = left_calls[group]
+            .iter()
+            .any(|callee| visit(callee, left_calls, visiting, done));
+        visiting.remove(group);
+        done.insert(group);
+        cycle
+    }
+    let mut visiting = BitSet::new();
+    let mut done = BitSet::new();
+    for group in 0..info.end_group {
+        if visit(group, &left_calls, &mut visiting, &mut done) {
+            return Err(Error::InfiniteLeftRecursion);
+        }
+    }
+    Ok(())
+}
+
+/// Collect the calls of each group that can happen before it matched anything. `at_left` are the
+/// groups for which nothing has been matched yet at the start of `info`.
+fn collect_left_calls(info: &Info<'_>, at_left: &[usize], left_calls: &mut [BitSet]) {
+    match *info.expr {
+        Expr::SubroutineCall(callee) => {
+            for &group in at_left {
+                left_calls[group].insert(callee);
+            }
+        }
+        Expr::Group(_) | Expr::NamedGroup { .. } => {
+            let mut groups = at_left.to_vec();
+            groups.push(info.start_group);
+            collect_left_calls(&info.children[0], &groups, left_calls);
+        }
+        Expr::Concat(_) => {
+            let mut min_size: usize = 0;
+            for child in &info.children {
+                let groups = if min_size == 0 { at_left } else { &[] };
+                collect_left_calls(child, groups, left_calls);
+                min_size = min_size.saturating_add(child.min_size);
+            }
+        }
+        // never matched
+        Expr::Repeat { hi: 0, .. } => {}
+        Expr::Conditional { .. } => {
+            let condition = &info.children[0];
+            collect_left_calls(condition, at_left, left_calls);
+            let groups = if condition.min_size == 0 {
+                at_left
+            } else {
+                &[]
+            };
+            collect_left_calls(&info.children[1], groups, left_calls);
+            collect_left_calls(&info.children[2], at_left, left_calls);
+        }
+        _ => {
+            for child in &info.children {
+                collect_left_calls(child, at_left, left_calls);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::analyze;
     use super::literal_const_size;
+    use crate::vm::Anchor;
     use crate::Expr;
-    use regex;
+    use bit_set::BitSet;
 
     #[test]
     fn case_folding_safe() {
@@ -228,12 +649,82 @@ mod tests {
         if re.is_match("\u{0565}\u{0582}") {
             assert!(!literal_const_size("\u{0587}", true));
         }
+
+        // Different lengths in bytes, but the same number of chars
+        let re = regex::Regex::new("(?i:k)").unwrap();
+        assert!(re.is_match("\u{212A}"));
+        assert!(literal_const_size("k", true));
+    }
+
+    #[test]
+    fn required_literal() {
+        fn required_literal(re: &str) -> Option<String> {
+            let (e, backrefs) = Expr::parse(re).unwrap();
+            analyze(&e, &backrefs).unwrap().required_literal(true)
+        }
+
+        assert_eq!(required_literal("abc"), Some(String::from("abc")));
+        assert_eq!(required_literal("(a|b|ab)*bc"), Some(String::from("bc")));
+        assert_eq!(required_literal("x(?=abc)"), Some(String::from("abc")));
+        assert_eq!(
+            required_literal("(foo)+(?!barbaz)"),
+            Some(String::from("foo"))
+        );
+        assert_eq!(
+            required_literal("(?<n>ab)cde\\w(f)"),
+            Some(String::from("cde"))
+        );
+        assert_eq!(required_literal("(ab)?c"), Some(String::from("c")));
+        assert_eq!(required_literal("(?i)abc"), None);
+        assert_eq!(required_literal("a|b"), None);
+        assert_eq!(required_literal("\\w+"), None);
+
+        // literals in look-behinds are before the start of the match
+        let (e, backrefs) = Expr::parse("(?<=abc)de").unwrap();
+        let info = analyze(&e, &backrefs).unwrap();
+        assert_eq!(info.required_literal(true), Some(String::from("abc")));
+        assert_eq!(info.required_literal(false), Some(String::from("de")));
+    }
+
+    #[test]
+    fn first_bytes() {
+        fn first_bytes(re: &str) -> Option<Vec<u8>> {
+            let (e, backrefs) = Expr::parse(re).unwrap();
+            let info = analyze(&e, &backrefs).unwrap();
+            let mut bytes = [false; 256];
+            match info.first_bytes(&mut bytes) {
+                Some(false) => Some((0..=255).filter(|&b| bytes[b as usize]).collect()),
+                _ => None,
+            }
+        }
+
+        assert_eq!(first_bytes("abc"), Some(b"a".to_vec()));
+        assert_eq!(first_bytes("(?>a|b)c"), Some(b"ab".to_vec()));
+        assert_eq!(first_bytes("\\b(?=x)(a|bc)?[d-f]"), Some(b"abdef".to_vec()));
+        assert_eq!(first_bytes("(?:ab|c)+"), Some(b"ac".to_vec()));
+        assert_eq!(first_bytes("é"), Some(vec![0xC3]));
+        // KELVIN SIGN is `K` with case folding
+        assert_eq!(first_bytes("(?i)k"), Some(vec![b'K', b'k', 0xE2]));
+        assert_eq!(first_bytes("(?i:[a-b])"), Some(b"ABab".to_vec()));
+        // can match without consuming anything
+        assert_eq!(first_bytes("a*"), None);
+        assert_eq!(first_bytes("a|(?=b)"), None);
+        // any char
+        assert_eq!(first_bytes(".a"), None);
     }
 
     #[test]
     fn invalid_backref_1() {
-        let (e, backrefs) = Expr::parse(".\\0").unwrap();
-        assert!(analyze(&e, &backrefs).is_err());
+        // the parser rejects a backref to group 0, so build it directly
+        let e = Expr::Concat(vec![
+            Expr::Any { newline: false },
+            Expr::Backref {
+                group: 0,
+                casei: false,
+                unicode: true,
+            },
+        ]);
+        assert!(analyze(&e, &BitSet::new()).is_err());
     }
 
     #[test]
@@ -248,17 +739,35 @@ mod tests {
         assert!(analyze(&e, &backrefs).is_err());
     }
 
+    #[test]
+    fn anchored_start() {
+        fn anchored_start(re: &str) -> Option<Anchor> {
+            let (e, backrefs) = Expr::parse(re).unwrap();
+            let info = analyze(&e, &backrefs).unwrap();
+            info.anchored_start()
+        }
+
+        assert_eq!(anchored_start("^a"), Some(Anchor::StartText));
+        assert_eq!(anchored_start("\\A(?>a|b)"), Some(Anchor::StartText));
+        assert_eq!(anchored_start("(?:^a|\\Ab)+"), Some(Anchor::StartText));
+        assert_eq!(anchored_start("(?m)^a"), Some(Anchor::StartLine));
+        assert_eq!(anchored_start("^a|(?m:^b)"), Some(Anchor::StartLine));
+        assert_eq!(anchored_start("a^"), None);
+        assert_eq!(anchored_start("^a|b"), None);
+        assert_eq!(anchored_start("(?:^a)?"), None);
+    }
+
     #[test]
     fn is_literal() {
         let (e, backrefs) = Expr::parse("abc").unwrap();
         let info = analyze(&e, &backrefs).unwrap();
-        assert_eq!(info.is_literal(), true);
+        assert!(info.is_literal());
     }
 
     #[test]
     fn is_literal_with_repeat() {
         let (e, backrefs) = Expr::parse("abc*").unwrap();
         let info = analyze(&e, &backrefs).unwrap();
-        assert_eq!(info.is_literal(), false);
+        assert!(!info.is_literal());
     }
 }
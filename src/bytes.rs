@@ -0,0 +1,682 @@
+// Copyright 2016 The Fancy Regex Authors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+/*!
+Regexes for matching on arbitrary bytes (`&[u8]`) instead of `&str`.
+
+The API is the same as the top-level one, but the text doesn't need to be valid UTF-8. Matching is
+delegated to `regex::bytes::Regex` of the [regex] crate.
+
+Unicode mode is enabled by default, so e.g. `.` matches a whole UTF-8 encoded char, and invalid
+UTF-8 is not matched by it. Unlike with the top-level `Regex`, Unicode mode can be disabled with
+the `u` flag, e.g. `(?-u:.)` matches any byte except `\n`, and `(?-u:\xFF)` matches the byte
+`0xFF`.
+
+# Example
+
+```rust
+use fancy_regex::bytes::Regex;
+
+let re = Regex::new(r"(?-u)(\xFF+)\1").unwrap();
+let text = b"\x00\xFF\xFF\xFF\xFF\x00";
+let m = re.find(text).unwrap().unwrap();
+
+assert_eq!(m.start(), 1);
+assert_eq!(m.as_bytes(), b"\xFF\xFF\xFF\xFF");
+```
+
+[regex]: https://crates.io/crates/regex
+*/
+
+use std::fmt;
+use std::ops::Index;
+use std::str;
+use std::sync::Arc;
+
+use crate::compile;
+use crate::vm::{self, Prog};
+use crate::{codepoint_len, prev_codepoint_ix};
+use crate::{compile_regex, CaptureNames, Compiled, NamedGroups, RegexOptions, Result};
+
+/// A builder for a bytes `Regex` to allow configuring options. It has the same options as the
+/// top-level [`RegexBuilder`](../struct.RegexBuilder.html).
+#[derive(Debug)]
+pub struct RegexBuilder(crate::RegexBuilder);
+
+/// A compiled regular expression for matching on bytes.
+pub struct Regex {
+    inner: RegexImpl,
+    named_groups: Arc<NamedGroups>,
+}
+
+enum RegexImpl {
+    Wrap {
+        inner: regex::bytes::Regex,
+        inner1: Option<Box<regex::bytes::Regex>>,
+        options: RegexOptions,
+    },
+    Fancy {
+        prog: Prog,
+        n_groups: usize,
+        options: RegexOptions,
+    },
+}
+
+/// A single match of a regex or group in an input text
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Match<'t> {
+    text: &'t [u8],
+    start: usize,
+    end: usize,
+}
+
+/// A set of capture groups found for a regex.
+#[derive(Debug)]
+pub struct Captures<'t> {
+    inner: CapturesImpl<'t>,
+    named_groups: Arc<NamedGroups>,
+}
+
+#[derive(Debug)]
+enum CapturesImpl<'t> {
+    Wrap {
+        text: &'t [u8],
+        inner: regex::bytes::Captures<'t>,
+
+        // starting position, in _from_pos variants
+        offset: usize,
+
+        enclosing_groups: usize,
+    },
+    Fancy {
+        text: &'t [u8],
+        saves: Vec<usize>,
+    },
+}
+
+/// An iterator over all non-overlapping matches for a particular text.
+///
+/// The iterator yields a `Result<Match>`. The iterator stops when no more
+/// matches can be found.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the matched text.
+#[derive(Debug)]
+pub struct Matches<'r, 't> {
+    re: &'r Regex,
+    text: &'t [u8],
+    last_end: usize,
+    last_match: Option<usize>,
+}
+
+/// An iterator that yields all non-overlapping capture groups matching a particular regular
+/// expression.
+///
+/// The iterator stops when no more matches can be found.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the matched text.
+#[derive(Debug)]
+pub struct CaptureMatches<'r, 't>(Matches<'r, 't>);
+
+/// Iterator for captured groups in order in which they appear in the regex.
+#[derive(Debug)]
+pub struct SubCaptureMatches<'c, 't> {
+    caps: &'c Captures<'t>,
+    i: usize,
+}
+
+impl fmt::Debug for Regex {
+    /// Shows the original regular expression.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl RegexBuilder {
+    /// Create a new regex builder with a regex pattern.
+    ///
+    /// If the pattern is invalid, the call to `build` will fail later.
+    pub fn new(pattern: &str) -> Self {
+        RegexBuilder(crate::RegexBuilder::new(pattern))
+    }
+
+    /// Build the `Regex`.
+    ///
+    /// Returns an [`Error`](../enum.Error.html) if the pattern could not be parsed.
+    pub fn build(&self) -> Result<Regex> {
+        Regex::new_options((self.0).0.clone())
+    }
+
+    /// See [`crate::RegexBuilder::case_insensitive`].
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.0.case_insensitive(yes);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::multi_line`].
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.0.multi_line(yes);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::dot_matches_new_line`].
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.0.dot_matches_new_line(yes);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::ignore_whitespace`].
+    pub fn ignore_whitespace(&mut self, yes: bool) -> &mut Self {
+        self.0.ignore_whitespace(yes);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::swap_greed`].
+    pub fn swap_greed(&mut self, yes: bool) -> &mut Self {
+        self.0.swap_greed(yes);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::backtrack_limit`].
+    pub fn backtrack_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.backtrack_limit(limit);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::recursion_limit`].
+    pub fn recursion_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.recursion_limit(limit);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::memoize`].
+    pub fn memoize(&mut self, yes: bool) -> &mut Self {
+        self.0.memoize(yes);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::memoize_size_limit`].
+    pub fn memoize_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.memoize_size_limit(limit);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::delegate_size_limit`].
+    pub fn delegate_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.delegate_size_limit(limit);
+        self
+    }
+
+    /// See [`crate::RegexBuilder::delegate_dfa_size_limit`].
+    pub fn delegate_dfa_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.delegate_dfa_size_limit(limit);
+        self
+    }
+}
+
+impl Regex {
+    /// Parse and compile a regex with default options.
+    ///
+    /// Returns an [`Error`](../enum.Error.html) if the pattern could not be parsed.
+    pub fn new(re: &str) -> Result<Regex> {
+        let options = RegexOptions {
+            pattern: re.to_string(),
+            ..RegexOptions::default()
+        };
+        Self::new_options(options)
+    }
+
+    fn new_options(options: RegexOptions) -> Result<Regex> {
+        let (compiled, named_groups) = compile_regex(&options, false)?;
+        let inner = match compiled {
+            Compiled::Wrap { re, re1 } => {
+                let inner = compile::compile_inner_bytes(&re, &options)?;
+                let inner1 = match re1 {
+                    Some(re1) => Some(Box::new(compile::compile_inner_bytes(&re1, &options)?)),
+                    None => None,
+                };
+                RegexImpl::Wrap {
+                    inner,
+                    inner1,
+                    options,
+                }
+            }
+            Compiled::Fancy { prog, n_groups, .. } => RegexImpl::Fancy {
+                prog,
+                n_groups,
+                options,
+            },
+        };
+        Ok(Regex {
+            inner,
+            named_groups,
+        })
+    }
+
+    /// Returns the original string of this regex.
+    pub fn as_str(&self) -> &str {
+        match &self.inner {
+            RegexImpl::Wrap { options, .. } => &options.pattern,
+            RegexImpl::Fancy { options, .. } => &options.pattern,
+        }
+    }
+
+    /// Returns an iterator over the capture names.
+    ///
+    /// The iterator yields an item for each capture group, including the implicit group `0` for
+    /// the whole match, which is `None` for unnamed groups.
+    pub fn capture_names(&self) -> CaptureNames<'_> {
+        let mut names = vec![None; self.captures_len()];
+        for (name, groups) in self.named_groups.iter() {
+            for &i in groups {
+                names[i] = Some(name.as_str());
+            }
+        }
+        CaptureNames(names.into_iter())
+    }
+
+    /// Returns the number of capture groups in this regex, including the implicit group `0` for
+    /// the whole match.
+    pub fn captures_len(&self) -> usize {
+        match &self.inner {
+            RegexImpl::Wrap { inner, .. } => inner.captures_len(),
+            RegexImpl::Fancy { n_groups, .. } => *n_groups,
+        }
+    }
+
+    /// Check if the regex matches the input text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fancy_regex::bytes::Regex;
+    ///
+    /// let re = Regex::new(r"(\w+) \1").unwrap();
+    /// assert!(re.is_match(b"\xFF mirror mirror").unwrap());
+    /// ```
+    pub fn is_match(&self, text: &[u8]) -> Result<bool> {
+        match &self.inner {
+            RegexImpl::Wrap { ref inner, .. } => Ok(inner.is_match(text)),
+            RegexImpl::Fancy {
+                ref prog, options, ..
+            } => {
+                let result = vm::run(prog, text, 0, 0, options)?;
+                Ok(result.is_some())
+            }
+        }
+    }
+
+    /// Find the first match in the input text.
+    pub fn find<'t>(&self, text: &'t [u8]) -> Result<Option<Match<'t>>> {
+        self.find_from_pos(text, 0)
+    }
+
+    /// Returns the first match in `text`, starting from the specified byte position `pos`.
+    ///
+    /// Like `captures_from_pos`, the text before `pos` is still taken into account for
+    /// look-behind and anchors like `\b` and `^`.
+    pub fn find_from_pos<'t>(&self, text: &'t [u8], pos: usize) -> Result<Option<Match<'t>>> {
+        Ok(self.find_consumed(text, pos)?.map(|(mat, _)| mat))
+    }
+
+    /// Like `find_from_pos`, but also returns the start of the text consumed by the match, see
+    /// `Prog::consumed_start`.
+    fn find_consumed<'t>(&self, text: &'t [u8], pos: usize) -> Result<Option<(Match<'t>, usize)>> {
+        match &self.inner {
+            RegexImpl::Wrap { inner, .. } => Ok(inner
+                .find_at(text, pos)
+                .map(|m| (Match::new(text, m.start(), m.end()), m.start()))),
+            RegexImpl::Fancy { prog, options, .. } => {
+                let result = vm::run(prog, text, pos, 0, options)?;
+                Ok(result.map(|saves| {
+                    let consumed_start = prog.consumed_start(&saves);
+                    (Match::new(text, saves[0], saves[1]), consumed_start)
+                }))
+            }
+        }
+    }
+
+    /// Returns an iterator for each successive non-overlapping match in `text`.
+    pub fn find_iter<'r, 't>(&'r self, text: &'t [u8]) -> Matches<'r, 't> {
+        Matches {
+            re: self,
+            text,
+            last_end: 0,
+            last_match: None,
+        }
+    }
+
+    /// Returns the capture groups for the first match in `text`.
+    ///
+    /// If no match is found, then `Ok(None)` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fancy_regex::bytes::Regex;
+    ///
+    /// let re = Regex::new(r"(?-u)(?<key>\w+)=(?<value>[^\x00]*)").unwrap();
+    /// let captures = re.captures(b"\x00lang=\xE9\x00").unwrap().unwrap();
+    ///
+    /// assert_eq!(&captures["key"], b"lang");
+    /// assert_eq!(&captures["value"], b"\xE9");
+    /// ```
+    pub fn captures<'t>(&self, text: &'t [u8]) -> Result<Option<Captures<'t>>> {
+        self.captures_from_pos(text, 0)
+    }
+
+    /// Returns an iterator over all the non-overlapping capture groups matched in `text`.
+    pub fn captures_iter<'r, 't>(&'r self, text: &'t [u8]) -> CaptureMatches<'r, 't> {
+        CaptureMatches(self.find_iter(text))
+    }
+
+    /// Returns the capture groups for the first match in `text`, starting from
+    /// the specified byte position `pos`.
+    pub fn captures_from_pos<'t>(
+        &self,
+        text: &'t [u8],
+        pos: usize,
+    ) -> Result<Option<Captures<'t>>> {
+        match &self.inner {
+            RegexImpl::Wrap { inner, inner1, .. } => {
+                if inner1.is_none() || pos == 0 {
+                    let result = inner.captures(&text[pos..]);
+                    Ok(result.map(|caps| Captures {
+                        inner: CapturesImpl::Wrap {
+                            text,
+                            inner: caps,
+                            offset: pos,
+                            enclosing_groups: 0,
+                        },
+                        named_groups: self.named_groups.clone(),
+                    }))
+                } else {
+                    let ix = prev_codepoint_ix(text, pos);
+                    let inner1 = inner1.as_ref().unwrap();
+                    let result = inner1.captures(&text[ix..]);
+                    Ok(result.map(|caps| Captures {
+                        inner: CapturesImpl::Wrap {
+                            text,
+                            inner: caps,
+                            offset: ix,
+                            enclosing_groups: 1,
+                        },
+                        named_groups: self.named_groups.clone(),
+                    }))
+                }
+            }
+            RegexImpl::Fancy { .. } => Ok(self.captures_consumed(text, pos)?.map(|(caps, _)| caps)),
+        }
+    }
+
+    /// Like `captures_from_pos`, but also returns the start of the text consumed by the match,
+    /// see `Prog::consumed_start`.
+    fn captures_consumed<'t>(
+        &self,
+        text: &'t [u8],
+        pos: usize,
+    ) -> Result<Option<(Captures<'t>, usize)>> {
+        match &self.inner {
+            RegexImpl::Wrap { .. } => Ok(self.captures_from_pos(text, pos)?.map(|caps| {
+                let start = caps.get(0).unwrap().start();
+                (caps, start)
+            })),
+            RegexImpl::Fancy {
+                prog,
+                n_groups,
+                options,
+            } => {
+                let result = vm::run(prog, text, pos, 0, options)?;
+                Ok(result.map(|mut saves| {
+                    let consumed_start = prog.consumed_start(&saves);
+                    saves.truncate(n_groups * 2);
+                    let captures = Captures {
+                        inner: CapturesImpl::Fancy { text, saves },
+                        named_groups: self.named_groups.clone(),
+                    };
+                    (captures, consumed_start)
+                }))
+            }
+        }
+    }
+}
+
+impl<'t> Match<'t> {
+    /// Returns the starting byte offset of the match in the text.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the ending byte offset of the match in the text.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the matched text.
+    #[inline]
+    pub fn as_bytes(&self) -> &'t [u8] {
+        &self.text[self.start..self.end]
+    }
+
+    fn new(text: &'t [u8], start: usize, end: usize) -> Match<'t> {
+        Match { text, start, end }
+    }
+}
+
+impl<'r, 't> Iterator for Matches<'r, 't> {
+    type Item = Result<Match<'t>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.last_end > self.text.len() {
+            return None;
+        }
+
+        let (mat, consumed_start) = match self.re.find_consumed(self.text, self.last_end) {
+            Err(error) => {
+                // Stop after the error instead of running the same search again
+                self.last_end = self.text.len() + 1;
+                return Some(Err(error));
+            }
+            Ok(None) => return None,
+            Ok(Some(found)) => found,
+        };
+
+        // With `\K`, an empty match can still have consumed text before it, e.g. for `a\K`. Then
+        // the next search starts at its end, like after a non-empty match.
+        if consumed_start == mat.end {
+            // This is an empty match. To ensure we make progress, start the next search at the
+            // next char.
+            self.last_end = next_char_ix(self.text, mat.end);
+            // Don't accept empty matches immediately following a match, just move on to the next
+            // match (same as the regex crate).
+            if Some(mat.end) == self.last_match {
+                return self.next();
+            }
+        } else {
+            self.last_end = mat.end;
+        }
+
+        self.last_match = Some(mat.end);
+        Some(Ok(mat))
+    }
+}
+
+impl<'r, 't> Iterator for CaptureMatches<'r, 't> {
+    type Item = Result<Captures<'t>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let matches = &mut self.0;
+        if matches.last_end > matches.text.len() {
+            return None;
+        }
+
+        let (captures, consumed_start) =
+            match matches.re.captures_consumed(matches.text, matches.last_end) {
+                Err(error) => {
+                    // Same as in `Matches`, stop after the error
+                    matches.last_end = matches.text.len() + 1;
+                    return Some(Err(error));
+                }
+                Ok(None) => return None,
+                Ok(Some(found)) => found,
+            };
+        let mat = captures
+            .get(0)
+            .expect("captures always contain the whole match");
+
+        // Same empty match handling as in `Matches`
+        if consumed_start == mat.end {
+            matches.last_end = next_char_ix(matches.text, mat.end);
+            if Some(mat.end) == matches.last_match {
+                return self.next();
+            }
+        } else {
+            matches.last_end = mat.end;
+        }
+
+        matches.last_match = Some(mat.end);
+        Some(Ok(captures))
+    }
+}
+
+impl<'t> Captures<'t> {
+    /// Get the capture group by its index in the regex.
+    ///
+    /// If there is no match for that group or the index does not correspond to a group, `None` is
+    /// returned. The index 0 returns the whole match.
+    pub fn get(&self, i: usize) -> Option<Match<'t>> {
+        match &self.inner {
+            CapturesImpl::Wrap {
+                text,
+                inner,
+                offset,
+                enclosing_groups,
+            } => inner.get(i + *enclosing_groups).map(|m| Match {
+                text,
+                start: m.start() + *offset,
+                end: m.end() + *offset,
+            }),
+            CapturesImpl::Fancy { text, ref saves } => {
+                let slot = i * 2;
+                if slot >= saves.len() {
+                    return None;
+                }
+                let lo = saves[slot];
+                if lo == usize::MAX {
+                    return None;
+                }
+                let hi = saves[slot + 1];
+                Some(Match {
+                    text,
+                    start: lo,
+                    end: hi,
+                })
+            }
+        }
+    }
+
+    /// Get the capture group by its name in the regex, e.g. `(?<name>...)`.
+    ///
+    /// If there is no match for that group or the regex doesn't have a group with that name,
+    /// `None` is returned. If multiple groups have the name, the last one of them that matched is
+    /// returned.
+    pub fn name(&self, name: &str) -> Option<Match<'t>> {
+        let groups = self.named_groups.get(name)?;
+        groups.iter().rev().find_map(|&i| self.get(i))
+    }
+
+    /// Iterate over the captured groups in order in which they appeared in the regex. The first
+    /// capture corresponds to the whole match.
+    pub fn iter<'c>(&'c self) -> SubCaptureMatches<'c, 't> {
+        SubCaptureMatches { caps: self, i: 0 }
+    }
+
+    /// How many groups were captured.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self.inner {
+            CapturesImpl::Wrap {
+                ref inner,
+                enclosing_groups,
+                ..
+            } => inner.len() - enclosing_groups,
+            CapturesImpl::Fancy { ref saves, .. } => saves.len() / 2,
+        }
+    }
+}
+
+/// Get a group by index.
+///
+/// # Panics
+///
+/// If there is no group at the given index.
+impl<'t> Index<usize> for Captures<'t> {
+    type Output = [u8];
+
+    fn index(&self, i: usize) -> &[u8] {
+        self.get(i)
+            .map(|m| m.as_bytes())
+            .unwrap_or_else(|| panic!("no group at index '{}'", i))
+    }
+}
+
+/// Get a group by name.
+///
+/// # Panics
+///
+/// If there is no group named by the given value.
+impl<'t, 'i> Index<&'i str> for Captures<'t> {
+    type Output = [u8];
+
+    fn index(&self, name: &'i str) -> &[u8] {
+        self.name(name)
+            .map(|m| m.as_bytes())
+            .unwrap_or_else(|| panic!("no group named '{}'", name))
+    }
+}
+
+impl<'c, 't> Iterator for SubCaptureMatches<'c, 't> {
+    type Item = Option<Match<'t>>;
+
+    fn next(&mut self) -> Option<Option<Match<'t>>> {
+        if self.i < self.caps.len() {
+            let result = self.caps.get(self.i);
+            self.i += 1;
+            Some(result)
+        } else {
+            None
+        }
+    }
+}
+
+// Returns the index after the char at `ix`, or `ix + 1` if the text at `ix` is not valid UTF-8 or
+// at the end of the text.
+fn next_char_ix(text: &[u8], ix: usize) -> usize {
+    if ix < text.len() {
+        let end = ix + codepoint_len(text[ix]);
+        if end <= text.len() && str::from_utf8(&text[ix..end]).is_ok() {
+            return end;
+        }
+    }
+    ix + 1
+}
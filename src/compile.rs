@@ -20,11 +20,11 @@
 
 //! Compilation of regexes to VM.
 
-use regex;
-use std::usize;
+use bit_set::BitSet;
+use std::collections::HashMap;
 
-use crate::analyze::Info;
-use crate::vm::{Insn, Prog};
+use crate::analyze::{parse_delegate, Info};
+use crate::vm::{CaseiLiteral, CharClass, Insn, Prefilter, Prog};
 use crate::Error;
 use crate::Expr;
 use crate::LookAround;
@@ -37,6 +37,13 @@ use crate::Result;
 struct VMBuilder {
     prog: Vec<Insn>,
     n_saves: usize,
+    text_end: Option<usize>,
+    match_start: Option<usize>,
+    /// How deeply the instructions that are added are nested in constructs whose matching
+    /// depends on saved state (e.g. the start of a look-around), see `Prog::memo_pcs`
+    stateful_depth: usize,
+    /// The instructions that are not in such a construct
+    memo_pcs: BitSet,
 }
 
 impl VMBuilder {
@@ -44,11 +51,41 @@ impl VMBuilder {
         VMBuilder {
             prog: Vec::new(),
             n_saves: max_group * 2,
+            text_end: None,
+            match_start: None,
+            stateful_depth: 0,
+            memo_pcs: BitSet::new(),
         }
     }
 
     fn build(self) -> Prog {
-        Prog::new(self.prog, self.n_saves)
+        // Whether a state can fail depends on more than the instruction and the index with
+        // backrefs (the captured text), calls (the return address) and absent operators (the end
+        // of the text).
+        let memo_pcs = if self.prog.iter().any(|insn| {
+            matches!(
+                insn,
+                Insn::Backref(_)
+                    | Insn::BackrefCasei(_)
+                    | Insn::BackrefAsciiCasei(_)
+                    | Insn::BackrefExistsCondition(_)
+                    | Insn::Call { .. }
+                    | Insn::BeginGroup
+                    | Insn::LimitEnd(_)
+                    | Insn::ClearEnd
+            )
+        }) {
+            None
+        } else {
+            Some(self.memo_pcs)
+        };
+        Prog::new(
+            self.prog,
+            self.n_saves,
+            self.text_end,
+            self.match_start,
+            memo_pcs,
+        )
     }
 
     fn newsave(&mut self) -> usize {
@@ -63,6 +100,9 @@ impl VMBuilder {
 
     // would "emit" be a better name?
     fn add(&mut self, insn: Insn) {
+        if self.stateful_depth == 0 {
+            self.memo_pcs.insert(self.prog.len());
+        }
         self.prog.push(insn);
     }
 
@@ -81,6 +121,22 @@ impl VMBuilder {
         }
     }
 
+    fn set_call_target(&mut self, call_pc: usize, subroutine: &Subroutine) {
+        match self.prog[call_pc] {
+            Insn::Call {
+                ref mut target,
+                ref mut slots_start,
+                ref mut slots_end,
+                ..
+            } => {
+                *target = subroutine.pc;
+                *slots_start = subroutine.slots_start;
+                *slots_end = subroutine.slots_end;
+            }
+            _ => panic!("mutating instruction other than Call"),
+        }
+    }
+
     fn set_repeat_target(&mut self, repeat_pc: usize, target: usize) {
         match self.prog[repeat_pc] {
             Insn::RepeatGr { ref mut next, .. }
@@ -92,12 +148,44 @@ impl VMBuilder {
     }
 }
 
+/// A group that is the target of subroutine calls, compiled once and called from each call site.
+struct Subroutine {
+    /// The first instruction of the subroutine
+    pc: usize,
+    /// The range of slots that are used by the body of the group (e.g. for repeat counts), which
+    /// need to be saved on recursion
+    slots_start: usize,
+    slots_end: usize,
+}
+
 struct Compiler {
     b: VMBuilder,
     options: RegexOptions,
+    /// The groups that are the target of subroutine calls
+    called_groups: BitSet,
+    /// The groups that contain subroutine calls, so they can be entered again before they end
+    groups_with_calls: BitSet,
+    /// The compiled subroutines by group number
+    subroutines: HashMap<usize, Subroutine>,
+    /// Instructions for calls that need the target filled in, with the called group
+    calls: Vec<(usize, usize)>,
+    /// The slot for keeping track of the depth of nested subroutine calls
+    call_depth: usize,
 }
 
 impl Compiler {
+    fn new(max_group: usize) -> Compiler {
+        Compiler {
+            b: VMBuilder::new(max_group),
+            options: Default::default(),
+            called_groups: BitSet::new(),
+            groups_with_calls: BitSet::new(),
+            subroutines: HashMap::new(),
+            calls: Vec::new(),
+            call_depth: usize::MAX,
+        }
+    }
+
     fn visit(&mut self, info: &Info<'_>, hard: bool) -> Result<()> {
         if !hard && !info.hard {
             // easy case, delegate entire subexpr
@@ -105,7 +193,7 @@ impl Compiler {
         }
         match *info.expr {
             Expr::Empty => (),
-            Expr::Literal { ref val, casei } => {
+            Expr::Literal { ref val, casei, .. } => {
                 if !casei {
                     self.b.add(Insn::Lit(val.clone()));
                 } else {
@@ -121,13 +209,30 @@ impl Compiler {
             Expr::Concat(_) => {
                 self.compile_concat(info, hard)?;
             }
-            Expr::Alt(_) => {
+            Expr::Alt(_) | Expr::BranchReset(_) => {
                 let count = info.children.len();
                 self.compile_alt(count, |compiler, i| compiler.visit(&info.children[i], hard))?;
             }
-            Expr::Group(_) => {
+            Expr::Group(_) | Expr::NamedGroup { .. }
+                if self.called_groups.contains(info.start_group) =>
+            {
+                self.compile_subroutine(info)?;
+            }
+            Expr::Group(_) | Expr::NamedGroup { .. }
+                if self.groups_with_calls.contains(info.start_group) =>
+            {
+                self.b.add(Insn::BeginGroup);
+                self.visit(&info.children[0], hard)?;
+                self.b.add(Insn::EndGroup(info.start_group * 2));
+            }
+            Expr::Group(_) | Expr::NamedGroup { .. } => {
                 let group = info.start_group;
                 self.b.add(Insn::Save(group * 2));
+                if group == 0 {
+                    if let Some(slot) = self.b.match_start {
+                        self.b.add(Insn::Save(slot));
+                    }
+                }
                 self.visit(&info.children[0], hard)?;
                 self.b.add(Insn::Save(group * 2 + 1));
             }
@@ -135,17 +240,65 @@ impl Compiler {
                 self.compile_repeat(info, lo, hi, greedy, hard)?;
             }
             Expr::LookAround(_, la) => {
+                self.b.stateful_depth += 1;
                 self.compile_lookaround(info, la)?;
+                self.b.stateful_depth -= 1;
+            }
+            Expr::Backref {
+                group,
+                casei,
+                unicode,
+            } => {
+                self.add_backref(vec![group * 2], casei, unicode);
+            }
+            Expr::MultiBackref {
+                ref groups,
+                casei,
+                unicode,
+            } => {
+                // the last group with the name is tried first
+                let slots = groups.iter().rev().map(|group| group * 2).collect();
+                self.add_backref(slots, casei, unicode);
+            }
+            Expr::ContinueFromPreviousMatchEnd => {
+                self.b.add(Insn::ContinueFromPreviousMatchEnd);
+            }
+            Expr::KeepOut => {
+                // overwrite the start of group 0, i.e. the whole match
+                self.b.add(Insn::Save(0));
             }
-            Expr::Backref(group) => {
-                self.b.add(Insn::Backref(group * 2));
+            Expr::SubroutineCall(group) => {
+                self.add_call(group);
+            }
+            Expr::BackrefExistsCondition(group) => {
+                self.b.add(Insn::BackrefExistsCondition(group * 2 + 1));
+            }
+            Expr::Conditional { .. } => {
+                self.b.stateful_depth += 1;
+                self.compile_conditional(info, hard)?;
+                self.b.stateful_depth -= 1;
+            }
+            Expr::Absent { .. } => {
+                let save = self.compile_absent(&info.children[0])?;
+                // The limit is removed again after the child, so what follows can't be delegated
+                // together with it, and the child may need to backtrack.
+                self.visit(&info.children[1], true)?;
+                self.b.add(Insn::RestoreEnd(save));
+            }
+            Expr::AbsentStopper(_) => {
+                self.compile_absent(&info.children[0])?;
+            }
+            Expr::AbsentRangeClear => {
+                self.b.add(Insn::ClearEnd);
             }
             Expr::AtomicGroup(_) => {
                 // TODO optimization: atomic insns are not needed if the
                 // child doesn't do any backtracking.
+                self.b.stateful_depth += 1;
                 self.b.add(Insn::BeginAtomic);
                 self.visit(&info.children[0], false)?;
                 self.b.add(Insn::EndAtomic);
+                self.b.stateful_depth -= 1;
             }
             Expr::Delegate { .. }
             | Expr::StartText
@@ -159,6 +312,29 @@ impl Compiler {
         Ok(())
     }
 
+    fn add_backref(&mut self, slots: Vec<usize>, casei: bool, unicode: bool) {
+        if !casei {
+            self.b.add(Insn::Backref(slots));
+        } else if unicode {
+            self.b.add(Insn::BackrefCasei(slots));
+        } else {
+            self.b.add(Insn::BackrefAsciiCasei(slots));
+        }
+    }
+
+    fn compile_search(&mut self, info: &Info<'_>, prefilter: Prefilter) -> Result<()> {
+        let find_pc = self.b.pc();
+        self.b.add(Insn::FindStart(Box::new(prefilter)));
+        let split_pc = self.b.pc();
+        self.b.add(Insn::Split(usize::MAX, split_pc + 1));
+        // skip one char of the `.*?`, then find the next start again
+        self.visit(&info.children[0].children[0], true)?;
+        self.b.add(Insn::Jmp(find_pc));
+        let pc = self.b.pc();
+        self.b.set_split_target(split_pc, pc, false);
+        self.visit(&info.children[1], false)
+    }
+
     fn compile_alt<F>(&mut self, count: usize, mut handle_alternative: F) -> Result<()>
     where
         F: FnMut(&mut Compiler, usize) -> Result<()>,
@@ -195,7 +371,7 @@ impl Compiler {
     }
 
     fn compile_concat(&mut self, info: &Info<'_>, hard: bool) -> Result<()> {
-        let children: Vec<_> = info.children.iter().map(|c| c).collect();
+        let children: Vec<_> = info.children.iter().collect();
 
         // First: determine a prefix which is constant size and not hard.
         let mut prefix_end = 0;
@@ -220,11 +396,28 @@ impl Compiler {
 
         self.compile_delegates(&children[..prefix_end])?;
 
-        if prefix_end < suffix_begin {
-            for child in children[prefix_end..suffix_begin - 1].iter() {
-                self.visit(child, true)?;
+        let mut ix = prefix_end;
+        while ix < suffix_begin {
+            // adjacent literals are matched with a single instruction
+            let middle = &children[ix..suffix_begin];
+            let literals = middle.iter().take_while(|c| c.is_literal()).count();
+            let casei = middle[0].casei_literal();
+            let casei_literals = match casei {
+                Some(_) => middle
+                    .iter()
+                    .take_while(|c| c.casei_literal() == casei)
+                    .count(),
+                None => 0,
+            };
+            let count = literals.max(casei_literals);
+            if count > 1 {
+                self.compile_delegates(&middle[..count])?;
+                ix += count;
+            } else {
+                let child_hard = if ix == suffix_begin - 1 { hard } else { true };
+                self.visit(children[ix], child_hard)?;
+                ix += 1;
             }
-            self.visit(children[suffix_begin - 1], hard)?;
         }
 
         self.compile_delegates(&children[suffix_begin..])
@@ -239,6 +432,16 @@ impl Compiler {
         hard: bool,
     ) -> Result<()> {
         let child = &info.children[0];
+        if greedy {
+            if let Some(class) = char_class(child) {
+                self.b.add(Insn::ClassRepeat {
+                    class: Box::new(class),
+                    lo,
+                    hi,
+                });
+                return Ok(());
+            }
+        }
         if lo == 0 && hi == 1 {
             // e?
             let pc = self.b.pc();
@@ -256,6 +459,7 @@ impl Compiler {
             // Use RepeatEpsilon instructions to prevent empty repeat
             let repeat = self.b.newsave();
             let check = self.b.newsave();
+            self.b.stateful_depth += 1;
             self.b.add(Insn::Save0(repeat));
             let pc = self.b.pc();
             if greedy {
@@ -275,6 +479,7 @@ impl Compiler {
             }
             self.visit(child, hard)?;
             self.b.add(Insn::Jmp(pc));
+            self.b.stateful_depth -= 1;
             let next_pc = self.b.pc();
             self.b.set_repeat_target(pc, next_pc);
         } else if lo == 0 && hi == usize::MAX {
@@ -294,6 +499,7 @@ impl Compiler {
             self.b.add(Insn::Split(x, y));
         } else {
             let repeat = self.b.newsave();
+            self.b.stateful_depth += 1;
             self.b.add(Insn::Save0(repeat));
             let pc = self.b.pc();
             if greedy {
@@ -313,6 +519,7 @@ impl Compiler {
             }
             self.visit(child, hard)?;
             self.b.add(Insn::Jmp(pc));
+            self.b.stateful_depth -= 1;
             let next_pc = self.b.pc();
             self.b.set_repeat_target(pc, next_pc);
         }
@@ -389,12 +596,100 @@ impl Compiler {
         self.visit(inner, false)
     }
 
+    fn compile_conditional(&mut self, info: &Info<'_>, hard: bool) -> Result<()> {
+        // The condition is in an atomic group so that once it matched, failing to match the true
+        // branch backtracks past the split instead of trying the false branch.
+        self.b.add(Insn::BeginAtomic);
+        let split_pc = self.b.pc();
+        self.b.add(Insn::Split(split_pc + 1, usize::MAX));
+        self.visit(&info.children[0], hard)?;
+        self.b.add(Insn::EndAtomic);
+
+        self.visit(&info.children[1], hard)?;
+        let jmp_pc = self.b.pc();
+        self.b.add(Insn::Jmp(0));
+
+        let false_pc = self.b.pc();
+        self.b.set_split_target(split_pc, false_pc, true);
+        // The split in the atomic group was taken, so the stack entry of `BeginAtomic` is still
+        // there and has to be removed.
+        self.b.add(Insn::EndAtomic);
+        self.visit(&info.children[2], hard)?;
+        let next_pc = self.b.pc();
+        self.b.set_jmp_target(jmp_pc, next_pc);
+        Ok(())
+    }
+
+    fn compile_subroutine(&mut self, info: &Info<'_>) -> Result<()> {
+        // Call the group where it's defined, and jump over its instructions
+        self.add_call(info.start_group);
+        let jmp_pc = self.b.pc();
+        self.b.add(Insn::Jmp(0));
+
+        let pc = self.b.pc();
+        let slots_start = self.b.n_saves;
+        let slot = info.start_group * 2;
+        // The group can be entered again (recursion) before it ends, so keep the start on the
+        // stack instead of in the slot. The capture of the group that ends last wins.
+        self.b.add(Insn::BeginGroup);
+        // Anything can follow a call, so the body has to be compiled as hard
+        self.visit(&info.children[0], true)?;
+        self.b.add(Insn::EndGroup(slot));
+        let slots_end = self.b.n_saves;
+        self.b.add(Insn::Ret {
+            slots_start,
+            slots_end,
+            depth: self.call_depth,
+        });
+        self.subroutines.insert(
+            info.start_group,
+            Subroutine {
+                pc,
+                slots_start,
+                slots_end,
+            },
+        );
+
+        let next_pc = self.b.pc();
+        self.b.set_jmp_target(jmp_pc, next_pc);
+        Ok(())
+    }
+
+    fn add_call(&mut self, group: usize) {
+        self.calls.push((self.b.pc(), group));
+        self.b.add(Insn::Call {
+            target: usize::MAX,
+            slots_start: 0,
+            slots_end: 0,
+            depth: self.call_depth,
+        });
+    }
+
+    /// Limit the end of the text to the first position where `absent` matches, returning the slot
+    /// with the previous end.
+    fn compile_absent(&mut self, absent: &Info<'_>) -> Result<usize> {
+        let start = self.b.newsave();
+        let save = self.b.newsave();
+        self.b.add(Insn::Save(start));
+        // Advance until `absent` matches, like `(?>(?:(?!absent)\O)*)`
+        self.b.add(Insn::BeginAtomic);
+        let split_pc = self.b.pc();
+        self.b.add(Insn::Split(split_pc + 1, usize::MAX));
+        self.compile_negative_lookaround(absent, LookAheadNeg)?;
+        self.b.add(Insn::Any);
+        self.b.add(Insn::Jmp(split_pc));
+        let next_pc = self.b.pc();
+        self.b.set_split_target(split_pc, next_pc, true);
+        self.b.add(Insn::EndAtomic);
+        self.b.add(Insn::LimitEnd(save));
+        self.b.add(Insn::Restore(start));
+        Ok(save)
+    }
+
     fn compile_delegates(&mut self, infos: &[&Info<'_>]) -> Result<()> {
         if infos.is_empty() {
             return Ok(());
         }
-        // TODO: might want to do something similar for case insensitive literals
-        // (have is_literal return an additional bool for casei)
         if infos.iter().all(|e| e.is_literal()) {
             let mut val = String::new();
             for info in infos {
@@ -403,6 +698,24 @@ impl Compiler {
             self.b.add(Insn::Lit(val));
             return Ok(());
         }
+        if let Some(unicode) = infos[0].casei_literal() {
+            if infos.iter().all(|e| e.casei_literal() == Some(unicode)) {
+                let mut val = String::new();
+                for info in infos {
+                    info.push_literal(&mut val);
+                }
+                let literal = CaseiLiteral::new(val, unicode);
+                self.b.add(Insn::LitCasei(Box::new(literal)));
+                return Ok(());
+            }
+        }
+        // A single class is matched directly instead of running a regex for one char
+        if let [info] = infos {
+            if let Some(class) = char_class(info) {
+                self.b.add(Insn::Class(Box::new(class)));
+                return Ok(());
+            }
+        }
         // TODO: might want to detect case of a group with no captures
         // inside, so we can run find() instead of captures()
         let mut annotated = String::new();
@@ -429,30 +742,22 @@ impl Compiler {
         }
         let start_group = infos[0].start_group;
         let end_group = infos[infos.len() - 1].end_group;
-        self.make_delegate(
-            &annotated,
-            min_size,
-            const_size,
-            looks_left,
-            start_group,
-            end_group,
-        )
+        self.make_delegate(&annotated, const_size, looks_left, start_group, end_group)
     }
 
     fn make_delegate(
         &mut self,
         inner_re: &str,
-        min_size: usize,
         const_size: bool,
         looks_left: bool,
         start_group: usize,
         end_group: usize,
     ) -> Result<()> {
-        let compiled = compile_inner(inner_re, &self.options)?;
+        let compiled = compile_inner_bytes(inner_re, &self.options)?;
         if looks_left {
             // The "s" flag is for allowing `.` to match `\n`
             let inner1 = ["^(?s:.)", &inner_re[1..]].concat();
-            let compiled1 = compile_inner(&inner1, &self.options)?;
+            let compiled1 = compile_inner_bytes(&inner1, &self.options)?;
             self.b.add(Insn::Delegate {
                 inner: Box::new(compiled),
                 inner1: Some(Box::new(compiled1)),
@@ -460,8 +765,7 @@ impl Compiler {
                 end_group,
             });
         } else if const_size && start_group == end_group {
-            let size = min_size;
-            self.b.add(Insn::DelegateSized(Box::new(compiled), size));
+            self.b.add(Insn::DelegateSized(Box::new(compiled)));
         } else {
             self.b.add(Insn::Delegate {
                 inner: Box::new(compiled),
@@ -474,6 +778,19 @@ impl Compiler {
     }
 }
 
+/// Returns the class if the expression is a delegate that matches a single char (or byte) of a
+/// class, e.g. `\w`, `[^a-z]` or `(?-u:.)`.
+fn char_class(info: &Info<'_>) -> Option<CharClass> {
+    match *info.expr {
+        Expr::Delegate {
+            ref inner,
+            size: 1,
+            casei,
+        } => CharClass::from_hir(&parse_delegate(inner, casei)?),
+        _ => None,
+    }
+}
+
 pub(crate) fn compile_inner(inner_re: &str, options: &RegexOptions) -> Result<regex::Regex> {
     let mut builder = regex::RegexBuilder::new(inner_re);
     if let Some(size_limit) = options.delegate_size_limit {
@@ -486,22 +803,109 @@ pub(crate) fn compile_inner(inner_re: &str, options: &RegexOptions) -> Result<re
     builder.build().map_err(Error::InnerError)
 }
 
+/// Like `compile_inner`, but for matching on bytes. This is used for the delegates in the VM, which
+/// runs on bytes so that it can be used for both `Regex` and `bytes::Regex`.
+pub(crate) fn compile_inner_bytes(
+    inner_re: &str,
+    options: &RegexOptions,
+) -> Result<regex::bytes::Regex> {
+    let mut builder = regex::bytes::RegexBuilder::new(inner_re);
+    if let Some(size_limit) = options.delegate_size_limit {
+        builder.size_limit(size_limit);
+    }
+    if let Some(dfa_size_limit) = options.delegate_dfa_size_limit {
+        builder.dfa_size_limit(dfa_size_limit);
+    }
+
+    builder.build().map_err(Error::InnerError)
+}
+
 /// Compile the analyzed expressions into a program.
 pub fn compile(info: &Info<'_>) -> Result<Prog> {
-    let mut c = Compiler {
-        b: VMBuilder::new(info.end_group),
-        options: Default::default(),
-    };
-    c.visit(info, false)?;
+    compile_with_prefilter(info, None)
+}
+
+/// Like `compile`, but for the expression built by `compile_regex`, i.e. `.*?` followed by the
+/// regex as group 0. With a prefilter, the `.*?` skips ahead to the positions where a match can
+/// start before trying to match.
+pub(crate) fn compile_with_prefilter(
+    info: &Info<'_>,
+    prefilter: Option<Prefilter>,
+) -> Result<Prog> {
+    let mut c = Compiler::new(info.end_group);
+    if uses_text_end(info) {
+        c.b.text_end = Some(c.b.newsave());
+    }
+    collect_calls(info, &mut c.called_groups, &mut c.groups_with_calls);
+    // The whole match can only be entered again with `(?R)`, keep saving it directly otherwise so
+    // that `\K` works.
+    if !c.called_groups.contains(0) {
+        c.groups_with_calls.remove(0);
+        if uses_keep_out(info) {
+            c.b.match_start = Some(c.b.newsave());
+        }
+    }
+    if !c.called_groups.is_empty() {
+        c.call_depth = c.b.newsave();
+        c.b.add(Insn::Save0(c.call_depth));
+    }
+    match prefilter {
+        Some(prefilter) => c.compile_search(info, prefilter)?,
+        None => c.visit(info, false)?,
+    }
     c.b.add(Insn::End);
+    for (call_pc, group) in c.calls.drain(..) {
+        match c.subroutines.get(&group) {
+            Some(subroutine) => c.b.set_call_target(call_pc, subroutine),
+            None => return Err(Error::InvalidSubroutineCall),
+        }
+    }
     Ok(c.b.build())
 }
 
+/// Whether the expression contains absent operators, which limit the end of the text
+fn uses_text_end(info: &Info<'_>) -> bool {
+    match *info.expr {
+        Expr::Absent { .. } | Expr::AbsentStopper(_) | Expr::AbsentRangeClear => true,
+        _ => info.children.iter().any(uses_text_end),
+    }
+}
+
+/// Whether the expression contains `\K`, which moves the start of the match
+fn uses_keep_out(info: &Info<'_>) -> bool {
+    match *info.expr {
+        Expr::KeepOut => true,
+        _ => info.children.iter().any(uses_keep_out),
+    }
+}
+
+/// Collect the groups that are called and the groups that contain calls, returning whether the
+/// expression contains a call.
+fn collect_calls(
+    info: &Info<'_>,
+    called_groups: &mut BitSet,
+    groups_with_calls: &mut BitSet,
+) -> bool {
+    let mut has_call = false;
+    if let Expr::SubroutineCall(group) = *info.expr {
+        called_groups.insert(group);
+        has_call = true;
+    }
+    for child in &info.children {
+        has_call |= collect_calls(child, called_groups, groups_with_calls);
+    }
+    if has_call && matches!(*info.expr, Expr::Group(_) | Expr::NamedGroup { .. }) {
+        groups_with_calls.insert(info.start_group);
+    }
+    has_call
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
     use crate::analyze::analyze;
+    use crate::parse::Parser;
     use bit_set::BitSet;
     use matches::assert_matches;
 
@@ -511,23 +915,23 @@ mod tests {
             Expr::Literal {
                 val: "a".into(),
                 casei: false,
+                unicode: true,
             },
             Expr::Literal {
                 val: "b".into(),
                 casei: false,
+                unicode: true,
             },
             Expr::Literal {
                 val: "c".into(),
                 casei: false,
+                unicode: true,
             },
         ]);
         let backrefs = BitSet::new();
         let info = analyze(&expr, &backrefs).unwrap();
 
-        let mut c = Compiler {
-            b: VMBuilder::new(0),
-            options: Default::default(),
-        };
+        let mut c = Compiler::new(0);
         // Force "hard" so that compiler doesn't just delegate
         c.visit(&info, true).unwrap();
         c.b.add(Insn::End);
@@ -544,4 +948,61 @@ mod tests {
         assert_matches!(prog[6], Insn::Lit(ref l) if l == "c");
         assert_matches!(prog[7], Insn::End);
     }
+    #[test]
+    fn char_classes() {
+        fn compile_hard(re: &str) -> Vec<Insn> {
+            let (expr, backrefs) = Parser::parse_bytes_with_flags(re, 0).unwrap();
+            let info = analyze(&expr, &backrefs).unwrap();
+            let mut c = Compiler::new(0);
+            c.visit(&info, true).unwrap();
+            c.b.prog
+        }
+
+        let prog = compile_hard("\\w{2,}[^a]+?");
+        assert_eq!(prog.len(), 3, "prog: {:?}", prog);
+        assert_matches!(
+            prog[0],
+            Insn::ClassRepeat {
+                ref class,
+                lo: 2,
+                hi: usize::MAX,
+            } if matches!(**class, CharClass::Unicode(_))
+        );
+        assert_matches!(prog[1], Insn::Class(ref class) if matches!(**class, CharClass::Unicode(_)));
+        assert_matches!(prog[2], Insn::Split(3, 1));
+
+        let prog = compile_hard("(?-u:[^a])");
+        assert_matches!(
+            prog[0],
+            Insn::Class(ref class) if matches!(**class, CharClass::Bytes(ref ranges) if ranges[..] == [(0, b'a' - 1), (b'a' + 1, 0xFF)])
+        );
+    }
+
+    #[test]
+    fn adjacent_literals() {
+        let (expr, backrefs) = Expr::parse("(?=a)bc(?i:de)f").unwrap();
+        let info = analyze(&expr, &backrefs).unwrap();
+        let mut c = Compiler::new(0);
+        c.visit(&info, true).unwrap();
+        let prog = c.b.prog;
+
+        assert_eq!(prog.len(), 6, "prog: {:?}", prog);
+        assert_matches!(prog[3], Insn::Lit(ref l) if l == "bc");
+        assert_matches!(prog[4], Insn::LitCasei(ref l) if l.as_str() == "de");
+        assert_matches!(prog[5], Insn::Lit(ref l) if l == "f");
+    }
+
+    #[test]
+    fn adjacent_literals_unicode_mode() {
+        // literals that ignore case differently are not merged
+        let (expr, backrefs) = Parser::parse_bytes_with_flags("(?=a)(?i:bc)(?i-u:de)", 0).unwrap();
+        let info = analyze(&expr, &backrefs).unwrap();
+        let mut c = Compiler::new(0);
+        c.visit(&info, true).unwrap();
+        let prog = c.b.prog;
+
+        assert_eq!(prog.len(), 5, "prog: {:?}", prog);
+        assert_matches!(prog[3], Insn::LitCasei(ref l) if l.as_str() == "bc");
+        assert_matches!(prog[4], Insn::LitCasei(ref l) if l.as_str() == "de");
+    }
 }
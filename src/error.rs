@@ -5,6 +5,7 @@ pub type Result<T> = ::std::result::Result<T, Error>;
 
 /// An error for the result of compiling or running a regex.
 #[derive(Debug, PartialEq)]
+#[allow(clippy::manual_non_exhaustive)]
 pub enum Error {
     // Compile time errors
     /// General parsing error
@@ -31,10 +32,18 @@ pub enum Error {
     InvalidClass,
     /// Unknown group flag
     UnknownFlag,
-    /// Disabling Unicode not supported
+    /// Disabling Unicode not supported (only supported by `bytes::Regex`)
     NonUnicodeUnsupported,
     /// Invalid back reference
     InvalidBackref,
+    /// Invalid or duplicate group name, e.g. `(?<1>a)`
+    InvalidGroupName,
+    /// `\K` inside a look-around assertion, e.g. `(?=a\K)`
+    KeepOutInLookAround,
+    /// Subroutine call to a group that doesn't exist, e.g. `\g<2>` with only one group
+    InvalidSubroutineCall,
+    /// Subroutine call that recurses infinitely without matching anything, e.g. `(?<n>a|\g<n>b)`
+    InfiniteLeftRecursion,
     /// Regex crate error
     InnerError(regex::Error),
 
@@ -45,6 +54,10 @@ pub enum Error {
     /// Configure using
     /// [`RegexBuilder::backtrack_limit`](struct.RegexBuilder.html#method.backtrack_limit).
     BacktrackLimitExceeded,
+    /// Max depth of nested subroutine calls exceeded while executing the regex.
+    /// Configure using
+    /// [`RegexBuilder::recursion_limit`](struct.RegexBuilder.html#method.recursion_limit).
+    RecursionLimitExceeded,
 
     /// This enum may grow additional variants, so this makes sure clients don't count on exhaustive
     /// matching. Otherwise, adding a new variant could break existing code.
@@ -77,9 +90,16 @@ impl fmt::Display for Error {
             Error::UnknownFlag => write!(f, "Unknown group flag"),
             Error::NonUnicodeUnsupported => write!(f, "Disabling Unicode not supported"),
             Error::InvalidBackref => write!(f, "Invalid back reference"),
+            Error::InvalidGroupName => write!(f, "Invalid or duplicate group name"),
+            Error::KeepOutInLookAround => write!(f, "\\K inside look-around assertion"),
+            Error::InvalidSubroutineCall => write!(f, "Subroutine call to non-existent group"),
+            Error::InfiniteLeftRecursion => write!(f, "Infinite left recursion"),
             Error::InnerError(e) => write!(f, "Regex error: {}", e),
             Error::StackOverflow => write!(f, "Max stack size exceeded for backtracking"),
             Error::BacktrackLimitExceeded => write!(f, "Max limit for backtracking count exceeded"),
+            Error::RecursionLimitExceeded => {
+                write!(f, "Max depth of nested subroutine calls exceeded")
+            }
             Error::__Nonexhaustive => unreachable!(),
         }
     }
@@ -86,6 +86,11 @@ let group = captures.get(1).expect("No group");
 assert_eq!(group.as_str(), "20");
 ```
 
+## Matching on bytes
+
+To match on text that is not necessarily valid UTF-8, use [`bytes::Regex`](bytes/struct.Regex.html).
+It also supports disabling Unicode mode with `(?-u)`, e.g. to match arbitrary bytes with `.`.
+
 # Syntax
 
 The regex syntax is based on the [regex] crate's, with some additional supported syntax. Escapes:
@@ -94,13 +99,70 @@ The regex syntax is based on the [regex] crate's, with some additional supported
 \h    hex digit ([0-9A-Fa-f])
 \H    not hex digit ([^0-9A-Fa-f])
 \e    escape control character (\x1B)
+\cx   control character, i.e. x with all but the lower 5 bits cleared, e.g. \cj (\n), also \C-x
+\M-x  meta character, i.e. x with the high bit (0x80) set, can be combined with \C-x
+\0nn  octal character code with a leading zero, up to 2 digits after the zero, e.g. \012 (\n)
+\nnn  octal character code, if nnn has several digits and the regex has fewer than nnn groups
+      (otherwise a backref), or always in a character class
+\o{n} octal character code, e.g. \o{101} (A)
+\G    the position where the search started, e.g. the end of the previous match with find_iter
+\K    keep out the text matched so far from the reported match, e.g. foo\Kbar matches bar
+\Z    end of text, or before a newline at the end of text
+\R    line break (\r\n, \n, \x0B, \x0C, \r, \u{85}, \u{2028} or \u{2029}), \r\n is never split
+\N    any character except new line, even with flag s
+\O    any character including new line, even without flag s
+\X    extended grapheme cluster, e.g. g\u{308} (a g with a diaeresis)
+\Q    quote everything up to \E (or the end) literally, e.g. \Q1+1\E, also in a character class
+```
+
+Comments, which are ignored (also without flag x):
+
+```norun
+(?#text)  comment, a `)` in the text has to be escaped with a backslash
 ```
 
+Flags, which can be set with `(?flags)` or only for a group with `(?flags:exp)`, and turned off
+after a `-`, e.g. `(?i-s)`:
+
+```norun
+i     case-insensitive
+m     multi-line mode: ^ and $ match at the start and end of lines
+s     allow . to match new line
+x     ignore whitespace and allow # comments to the end of the line
+U     swap the meaning of greedy and non-greedy repeats
+u     Unicode mode (on by default, can only be turned off with bytes::Regex)
+^     reset to the defaults (also the flags set with RegexBuilder), only as the first one, e.g. (?^i)
+```
+
+Flags that are set with `(?flags)` apply until the end of the enclosing group, including the
+alternatives that follow in the group like in PCRE and the regex crate, e.g. in `(a(?i)b|c)d`, `b`
+and `c` are case-insensitive, but `a` and `d` are not. Note that Oniguruma differs here, it treats
+`a(?i)b|c` as `a(?i:b|c)`.
+
+Named capture groups:
+
+```norun
+(?<name>exp)    match exp, creating capture group named name
+(?P<name>exp)   same as above, the syntax of the regex crate
+(?'name'exp)    same as above
+```
+
+Multiple groups can have the same name, e.g. `(?<x>a)|(?<x>b)`. A backref to the name tries the
+groups from the last one to the first and uses the first one that matched and whose text matches.
+`Captures::name` returns the last of the groups that matched.
+
+In a branch reset group `(?|exp1|exp2)`, the capture groups of each alternative are numbered
+starting from the same number, e.g. both `(a)` and `(b)` are group 1 in `(?|(a)|(b))c(d)`, and
+`(d)` is group 2.
+
 Backreferences:
 
 ```norun
-\1    match the exact string that the first capture group matched
-\2    backref to the second capture group, etc
+\1          match the exact string that the first capture group matched
+\2          backref to the second capture group, etc
+\k<name>    backref to the group named name, also \k'name', \k{name}, \g{name} and (?P=name)
+\k<-1>      relative backref to the last opened group, also \k'-1', \g{-1} and \g-1
+\g{1}       backref to the first group, also \g1
 ```
 
 Look-around assertions for matching without changing the current position:
@@ -122,6 +184,38 @@ assert!(re.is_match("abcc").unwrap());
 assert!(!re.is_match("abc").unwrap());
 ```
 
+Conditionals, where the first alternative has to match if the condition matches and the rest
+otherwise:
+
+```norun
+(?(1)yes|no)       yes if the first capture group matched, also (?(-1)...) for the last opened one
+(?(<name>)yes|no)  yes if the group named name matched, also (?('name')...) and (?(name)...)
+(?(?=exp)yes|no)   yes if the look-around assertion matches, also (?!exp), (?<=exp) and (?<!exp)
+(?(1)yes)          yes if the first capture group matched, otherwise nothing
+```
+
+Subroutine calls, which match the expression of a group again (and can be recursive):
+
+```norun
+\g<1>     the first capture group, also \g'1', (?1) and relative ones like \g<-1> and (?+1)
+\g<name>  the group named name, also \g'name', (?&name) and (?P>name)
+(?R)      the whole regex, also \g<0> and (?0)
+```
+
+The group can be defined after the call. A group that is matched by a call captures the text it
+matched, like in Oniguruma, e.g. `(a|b)\g<1>` on `ab` captures `b`. Calls that could recurse
+without matching anything, like `(a|(?1)b)`, are an error.
+
+Absent operators, which limit matching to the text before the first match of an expression
+(searching from where the operator is):
+
+```norun
+(?~abc)       any text that doesn't contain abc (longest first), e.g. <!--(?~-->)--> for comments
+(?~|abc|exp)  exp, but only matching text that doesn't contain abc
+(?~|abc)      limit the rest of the regex to text that doesn't contain abc
+(?~|)         remove the limit again
+```
+
 [regex]: https://crates.io/crates/regex
 */
 
@@ -130,21 +224,29 @@ assert!(!re.is_match("abc").unwrap());
 #![deny(missing_debug_implementations)]
 
 use bit_set::BitSet;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
-use std::usize;
+use std::ops::Index;
+use std::sync::Arc;
 
 mod analyze;
+pub mod bytes;
 mod compile;
 mod error;
 mod parse;
+mod regexset;
+mod replacer;
 mod vm;
 
 use crate::analyze::analyze;
-use crate::compile::compile;
+use crate::compile::compile_with_prefilter;
 use crate::parse::Parser;
-use crate::vm::Prog;
+use crate::vm::{Prefilter, Prog};
 
 pub use crate::error::{Error, Result};
+pub use crate::regexset::{RegexSet, SetMatches, SetMatchesIter};
+pub use crate::replacer::{NoExpand, Replacer, ReplacerRef};
 
 const MAX_RECURSION: usize = 64;
 
@@ -155,7 +257,12 @@ const MAX_RECURSION: usize = 64;
 pub struct RegexBuilder(RegexOptions);
 
 /// A compiled regular expression.
-pub struct Regex(RegexImpl);
+pub struct Regex {
+    inner: RegexImpl,
+    named_groups: Arc<NamedGroups>,
+}
+
+type NamedGroups = HashMap<String, Vec<usize>>;
 
 // Separate enum because we don't want to expose any of this
 enum RegexImpl {
@@ -182,7 +289,10 @@ pub struct Match<'t> {
 
 /// A set of capture groups found for a regex.
 #[derive(Debug)]
-pub struct Captures<'t>(CapturesImpl<'t>);
+pub struct Captures<'t> {
+    inner: CapturesImpl<'t>,
+    named_groups: Arc<NamedGroups>,
+}
 
 #[derive(Debug)]
 enum CapturesImpl<'t> {
@@ -201,6 +311,62 @@ enum CapturesImpl<'t> {
     },
 }
 
+/// An iterator over all non-overlapping matches for a particular string.
+///
+/// The iterator yields a `Result<Match>`. The iterator stops when no more
+/// matches can be found.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the matched string.
+#[derive(Debug)]
+pub struct Matches<'r, 't> {
+    re: &'r Regex,
+    text: &'t str,
+    last_end: usize,
+    last_match: Option<usize>,
+}
+
+/// An iterator that yields all non-overlapping capture groups matching a particular regular
+/// expression.
+///
+/// The iterator stops when no more matches can be found.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the matched string.
+#[derive(Debug)]
+pub struct CaptureMatches<'r, 't>(Matches<'r, 't>);
+
+/// An iterator over the substrings of a text that are delimited by matches of a regex.
+///
+/// The iterator yields a `Result<&str>`, see [`Regex::split`](struct.Regex.html#method.split).
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being split.
+#[derive(Debug)]
+pub struct Split<'r, 't> {
+    matches: Matches<'r, 't>,
+    last: usize,
+}
+
+/// An iterator over at most `N` substrings of a text that are delimited by matches of a regex.
+///
+/// The iterator yields a `Result<&str>`, see [`Regex::splitn`](struct.Regex.html#method.splitn).
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being split.
+#[derive(Debug)]
+pub struct SplitN<'r, 't> {
+    splits: Split<'r, 't>,
+    n: usize,
+}
+
+/// An iterator over the names of all possible capture groups, see
+/// [`Regex::capture_names`](struct.Regex.html#method.capture_names).
+///
+/// `'r` is the lifetime of the compiled regular expression.
+#[derive(Debug)]
+pub struct CaptureNames<'r>(std::vec::IntoIter<Option<&'r str>>);
+
 /// Iterator for captured groups in order in which they appear in the regex.
 #[derive(Debug)]
 pub struct SubCaptureMatches<'c, 't> {
@@ -212,12 +378,16 @@ pub struct SubCaptureMatches<'c, 't> {
 struct RegexOptions {
     pattern: String,
     backtrack_limit: usize,
+    recursion_limit: usize,
+    memoize: bool,
+    memoize_size_limit: usize,
     delegate_size_limit: Option<usize>,
     delegate_dfa_size_limit: Option<usize>,
     case_insensitive: bool,
     multi_line: bool,
     dot_matches_new_line: bool,
-    unicode: bool,
+    ignore_whitespace: bool,
+    swap_greed: bool,
 }
 
 impl Default for RegexOptions {
@@ -225,34 +395,50 @@ impl Default for RegexOptions {
         RegexOptions {
             pattern: String::new(),
             backtrack_limit: 1_000_000,
+            recursion_limit: 1_000,
+            memoize: false,
+            memoize_size_limit: 256 * (1 << 10),
             delegate_size_limit: None,
             delegate_dfa_size_limit: None,
             case_insensitive: false,
             multi_line: false,
             dot_matches_new_line: false,
-            unicode: false,
+            ignore_whitespace: false,
+            swap_greed: false,
         }
     }
 }
 
+impl RegexOptions {
+    /// The initial parser flags for these options.
+    fn compute_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.case_insensitive {
+            flags |= parse::FLAG_CASEI;
+        }
+        if self.multi_line {
+            flags |= parse::FLAG_MULTI;
+        }
+        if self.dot_matches_new_line {
+            flags |= parse::FLAG_DOTNL;
+        }
+        if self.ignore_whitespace {
+            flags |= parse::FLAG_IGNORE_SPACE;
+        }
+        if self.swap_greed {
+            flags |= parse::FLAG_SWAP_GREED;
+        }
+        flags
+    }
+}
+
 impl RegexBuilder {
     /// Create a new regex builder with a regex pattern.
     ///
     /// If the pattern is invalid, the call to `build` will fail later.
     pub fn new(pattern: &str) -> Self {
         let mut builder = RegexBuilder(RegexOptions::default());
-        if builder.0.case_insensitive || builder.0.multi_line || builder.0.dot_matches_new_line || builder.0.unicode {
-            let flags = format!(
-                "{}{}{}{}",
-                if builder.0.case_insensitive { "i" } else { "" },
-                if builder.0.multi_line { "m" } else { "" },
-                if builder.0.dot_matches_new_line { "s" } else { "" },
-                if builder.0.unicode { "u" } else { "" },
-            );
-            builder.0.pattern = format!("(?{}){}", &flags, pattern);
-        } else {
-            builder.0.pattern = pattern.to_string();
-        }
+        builder.0.pattern = pattern.to_string();
         builder
     }
 
@@ -263,6 +449,51 @@ impl RegexBuilder {
         Regex::new_options(self.0.clone())
     }
 
+    /// Set the value for the case insensitive (`i`) flag.
+    ///
+    /// When enabled, letters in the pattern will match both upper case and lower case variants.
+    /// This is the same as starting the pattern with `(?i)`.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.0.case_insensitive = yes;
+        self
+    }
+
+    /// Set the value for the multi-line matching (`m`) flag.
+    ///
+    /// When enabled, `^` matches at the beginning of lines and `$` matches at the end of lines.
+    /// This is the same as starting the pattern with `(?m)`.
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.0.multi_line = yes;
+        self
+    }
+
+    /// Set the value for the any character (`s`) flag.
+    ///
+    /// When enabled, `.` matches any character including `\n`. This is the same as starting the
+    /// pattern with `(?s)`.
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.0.dot_matches_new_line = yes;
+        self
+    }
+
+    /// Set the value for the ignore whitespace (`x`) flag.
+    ///
+    /// When enabled, whitespace in the pattern is ignored and `#` starts a comment that runs to
+    /// the end of the line. This is the same as starting the pattern with `(?x)`.
+    pub fn ignore_whitespace(&mut self, yes: bool) -> &mut Self {
+        self.0.ignore_whitespace = yes;
+        self
+    }
+
+    /// Set the value for the greedy swap (`U`) flag.
+    ///
+    /// When enabled, `a*` is lazy (tries to find the shortest match) and `a*?` is greedy (tries
+    /// to find the longest match). This is the same as starting the pattern with `(?U)`.
+    pub fn swap_greed(&mut self, yes: bool) -> &mut Self {
+        self.0.swap_greed = yes;
+        self
+    }
+
     /// Limit for how many times backtracking should be attempted for fancy regexes (where
     /// backtracking is used). If this limit is exceeded, execution returns an error with
     /// [`Error::BacktrackLimitExceeded`](enum.Error.html#variant.BacktrackLimitExceeded).
@@ -274,6 +505,46 @@ impl RegexBuilder {
         self
     }
 
+    /// Limit for how deeply subroutine calls (e.g. `\g<1>` or `(?R)`) can be nested when
+    /// matching. If this limit is exceeded, execution returns an error with
+    /// [`Error::RecursionLimitExceeded`](enum.Error.html#variant.RecursionLimitExceeded).
+    /// This is for preventing a recursive regex from using too much memory.
+    ///
+    /// Default is `1_000`.
+    pub fn recursion_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.recursion_limit = limit;
+        self
+    }
+
+    /// Enable memoization for fancy regexes: Remember which states (position in the regex and
+    /// in the text) have already been tried, so that backtracking doesn't try them again. This
+    /// makes the runtime linear in the length of the regex times the length of the text, e.g.
+    /// for `(a|b|ab)*(?=c)`, which would otherwise backtrack exponentially.
+    ///
+    /// States in look-arounds, atomic groups, conditionals and repeats with a count (e.g.
+    /// `{2,5}`) are not memoized, and regexes with backrefs, subroutine calls or absent
+    /// operators are not memoized at all. States further into the text than
+    /// [`memoize_size_limit`](#method.memoize_size_limit) allows are not memoized either.
+    ///
+    /// Regexes without fancy features are matched by the regex crate, which doesn't backtrack,
+    /// so this has no effect on them.
+    ///
+    /// Default is `false`.
+    pub fn memoize(&mut self, yes: bool) -> &mut Self {
+        self.0.memoize = yes;
+        self
+    }
+
+    /// Limit for how many bytes memoization (see [`memoize`](#method.memoize)) can use for a
+    /// search. One bit is needed for each instruction of the compiled regex and position in the
+    /// text that the search gets to.
+    ///
+    /// Default is `262_144` (256 KiB).
+    pub fn memoize_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.memoize_size_limit = limit;
+        self
+    }
+
     /// Set the approximate size limit of the compiled regular expression.
     ///
     /// This option is forwarded from the wrapped `regex` crate. Note that depending on the used
@@ -296,6 +567,107 @@ impl RegexBuilder {
     }
 }
 
+/// The result of `compile_regex`, before the regex crate is used to compile the delegated parts.
+enum Compiled {
+    /// The regex doesn't use any fancy features, so it can be wrapped. Contains the regex in the
+    /// regex crate's syntax, and a version of it for 1-char look-behind if needed.
+    Wrap { re: String, re1: Option<String> },
+    /// The regex needs the VM. Also contains the literal that every match has to contain, if
+    /// there is one.
+    Fancy {
+        prog: Prog,
+        n_groups: usize,
+        required_literal: Option<String>,
+    },
+}
+
+/// Parse, analyze and compile the regex in `options`. This is shared by `Regex` and
+/// `bytes::Regex`, where `utf8` is false to allow matching arbitrary bytes.
+fn compile_regex(options: &RegexOptions, utf8: bool) -> Result<(Compiled, Arc<NamedGroups>)> {
+    let flags = options.compute_flags();
+    let (raw_e, backrefs) = if utf8 {
+        Parser::parse_with_flags(&options.pattern, flags)?
+    } else {
+        Parser::parse_bytes_with_flags(&options.pattern, flags)?
+    };
+
+    // wrapper to search for re at arbitrary start position,
+    // and to capture the match bounds
+    let any = if utf8 {
+        Expr::Any { newline: true }
+    } else {
+        // any byte, so that the search also works for text that is not valid UTF-8
+        Expr::Delegate {
+            inner: String::from("(?s-u:.)"),
+            size: 1,
+            casei: false,
+        }
+    };
+    let e = Expr::Concat(vec![
+        Expr::Repeat {
+            child: Box::new(any),
+            lo: 0,
+            hi: usize::MAX,
+            greedy: false,
+        },
+        Expr::Group(Box::new(raw_e)),
+    ]);
+
+    let info = analyze(&e, &backrefs)?;
+
+    let mut named_groups = NamedGroups::new();
+    info.collect_group_names(&mut named_groups);
+    let named_groups = Arc::new(named_groups);
+
+    let inner_info = &info.children[1].children[0]; // references inner expr
+    if !inner_info.hard {
+        // easy case, wrap regex
+
+        // we do our own to_str because escapes are different
+        let mut re_cooked = String::new();
+        // same as raw_e above, but it was moved, so traverse to find it
+        let raw_e = match e {
+            Expr::Concat(ref v) => match v[1] {
+                Expr::Group(ref child) => child,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        raw_e.to_str(&mut re_cooked, 0);
+        let re1 = if inner_info.looks_left {
+            // create regex to handle 1-char look-behind
+            Some(["^(?s:.)+?(", re_cooked.as_str(), ")"].concat())
+        } else {
+            None
+        };
+        return Ok((Compiled::Wrap { re: re_cooked, re1 }, named_groups));
+    }
+
+    // When searching, skip the text where a match can't start (e.g. everything after the start
+    // for a regex that is anchored with `^`)
+    let mut first_bytes = [false; 256];
+    let first_bytes = match inner_info.first_bytes(&mut first_bytes) {
+        Some(false) => Some(first_bytes),
+        _ => None,
+    };
+    let prefilter = Prefilter::new(
+        inner_info.required_literal(false).as_deref(),
+        first_bytes,
+        inner_info.anchored_start(),
+    );
+    let prog = compile_with_prefilter(&info, prefilter)?;
+    let n_groups = info.end_group;
+    let required_literal = inner_info.required_literal(true);
+    Ok((
+        Compiled::Fancy {
+            prog,
+            n_groups,
+            required_literal,
+        },
+        named_groups,
+    ))
+}
+
 impl fmt::Debug for Regex {
     /// Shows the original regular expression.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -316,69 +688,81 @@ impl Regex {
     }
 
     fn new_options(options: RegexOptions) -> Result<Regex> {
-        let (raw_e, backrefs) = Expr::parse(&options.pattern)?;
-
-        // wrapper to search for re at arbitrary start position,
-        // and to capture the match bounds
-        let e = Expr::Concat(vec![
-            Expr::Repeat {
-                child: Box::new(Expr::Any { newline: true }),
-                lo: 0,
-                hi: usize::MAX,
-                greedy: false,
-            },
-            Expr::Group(Box::new(raw_e)),
-        ]);
-
-        let info = analyze(&e, &backrefs)?;
-
-        let inner_info = &info.children[1].children[0]; // references inner expr
-        if !inner_info.hard {
-            // easy case, wrap regex
+        let (compiled, named_groups) = compile_regex(&options, true)?;
+        Regex::from_compiled(compiled, named_groups, options)
+    }
 
-            // we do our own to_str because escapes are different
-            let mut re_cooked = String::new();
-            // same as raw_e above, but it was moved, so traverse to find it
-            let raw_e = match e {
-                Expr::Concat(ref v) => match v[1] {
-                    Expr::Group(ref child) => child,
-                    _ => unreachable!(),
-                },
-                _ => unreachable!(),
-            };
-            raw_e.to_str(&mut re_cooked, 0);
-            let inner = compile::compile_inner(&re_cooked, &options)?;
-            let inner1 = if inner_info.looks_left {
-                // create regex to handle 1-char look-behind
-                let re1 = ["^(?s:.)+?(", re_cooked.as_str(), ")"].concat();
-                let compiled = compile::compile_inner(&re1, &options)?;
-                Some(Box::new(compiled))
-            } else {
-                None
-            };
-            return Ok(Regex(RegexImpl::Wrap {
-                inner,
-                inner1,
+    fn from_compiled(
+        compiled: Compiled,
+        named_groups: Arc<NamedGroups>,
+        options: RegexOptions,
+    ) -> Result<Regex> {
+        let inner = match compiled {
+            Compiled::Wrap { re, re1 } => {
+                let inner = compile::compile_inner(&re, &options)?;
+                let inner1 = match re1 {
+                    Some(re1) => Some(Box::new(compile::compile_inner(&re1, &options)?)),
+                    None => None,
+                };
+                RegexImpl::Wrap {
+                    inner,
+                    inner1,
+                    options,
+                }
+            }
+            Compiled::Fancy { prog, n_groups, .. } => RegexImpl::Fancy {
+                prog,
+                n_groups,
                 options,
-            }));
-        }
-
-        let prog = compile(&info)?;
-        Ok(Regex(RegexImpl::Fancy {
-            prog,
-            n_groups: info.end_group,
-            options,
-        }))
+            },
+        };
+        Ok(Regex {
+            inner,
+            named_groups,
+        })
     }
 
     /// Returns the original string of this regex.
     pub fn as_str(&self) -> &str {
-        match &self.0 {
+        match &self.inner {
             RegexImpl::Wrap { options, .. } => &options.pattern,
             RegexImpl::Fancy { options, .. } => &options.pattern,
         }
     }
 
+    /// Returns an iterator over the capture names.
+    ///
+    /// The iterator yields an item for each capture group, including the implicit group `0` for
+    /// the whole match, which is `None` for unnamed groups.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"(?<year>\d{4})-(\d{2})").unwrap();
+    /// let names: Vec<_> = re.capture_names().collect();
+    /// assert_eq!(names, vec![None, Some("year"), None]);
+    /// ```
+    pub fn capture_names(&self) -> CaptureNames<'_> {
+        let mut names = vec![None; self.captures_len()];
+        for (name, groups) in self.named_groups.iter() {
+            for &i in groups {
+                names[i] = Some(name.as_str());
+            }
+        }
+        CaptureNames(names.into_iter())
+    }
+
+    /// Returns the number of capture groups in this regex, including the implicit group `0` for
+    /// the whole match.
+    pub fn captures_len(&self) -> usize {
+        match &self.inner {
+            RegexImpl::Wrap { inner, .. } => inner.captures_len(),
+            RegexImpl::Fancy { n_groups, .. } => *n_groups,
+        }
+    }
+
     /// Check if the regex matches the input text.
     ///
     /// # Example
@@ -392,12 +776,12 @@ impl Regex {
     /// assert!(re.is_match("mirror mirror on the wall").unwrap());
     /// ```
     pub fn is_match(&self, text: &str) -> Result<bool> {
-        match &self.0 {
+        match &self.inner {
             RegexImpl::Wrap { ref inner, .. } => Ok(inner.is_match(text)),
             RegexImpl::Fancy {
                 ref prog, options, ..
             } => {
-                let result = vm::run(prog, text, 0, 0, options)?;
+                let result = vm::run(prog, text.as_bytes(), 0, 0, options)?;
                 Ok(result.is_some())
             }
         }
@@ -419,17 +803,76 @@ impl Regex {
     /// assert_eq!(re.find("so fancy!").unwrap().unwrap().as_str(), "fancy");
     /// ```
     pub fn find<'t>(&self, text: &'t str) -> Result<Option<Match<'t>>> {
-        match &self.0 {
+        self.find_from_pos(text, 0)
+    }
+
+    /// Returns the first match in `text`, starting from the specified byte position `pos`.
+    ///
+    /// Like `captures_from_pos`, the text before `pos` is still taken into account for
+    /// look-behind and anchors like `\b` and `^`.
+    ///
+    /// # Examples
+    ///
+    /// Finding a match starting at a position:
+    ///
+    /// ```
+    /// # use fancy_regex::Regex;
+    /// let re = Regex::new(r"(?m:^)(\d+)").unwrap();
+    /// let text = "1 test 123\n2 foo";
+    /// let mat = re.find_from_pos(text, 7).unwrap().unwrap();
+    ///
+    /// assert_eq!(mat.start(), 11);
+    /// assert_eq!(mat.end(), 12);
+    /// ```
+    pub fn find_from_pos<'t>(&self, text: &'t str, pos: usize) -> Result<Option<Match<'t>>> {
+        Ok(self.find_consumed(text, pos)?.map(|(mat, _)| mat))
+    }
+
+    /// Like `find_from_pos`, but also returns the start of the text consumed by the match, see
+    /// `Prog::consumed_start`.
+    fn find_consumed<'t>(&self, text: &'t str, pos: usize) -> Result<Option<(Match<'t>, usize)>> {
+        match &self.inner {
             RegexImpl::Wrap { inner, .. } => Ok(inner
-                .find(text)
-                .map(|m| Match::new(text, m.start(), m.end()))),
+                .find_at(text, pos)
+                .map(|m| (Match::new(text, m.start(), m.end()), m.start()))),
             RegexImpl::Fancy { prog, options, .. } => {
-                let result = vm::run(prog, text, 0, 0, options)?;
-                Ok(result.map(|saves| Match::new(text, saves[0], saves[1])))
+                let result = vm::run(prog, text.as_bytes(), pos, 0, options)?;
+                Ok(result.map(|saves| {
+                    let consumed_start = prog.consumed_start(&saves);
+                    (Match::new(text, saves[0], saves[1]), consumed_start)
+                }))
             }
         }
     }
 
+    /// Returns an iterator for each successive non-overlapping match in `text`.
+    ///
+    /// If you have capturing groups in your regex that you want to extract, use the
+    /// [`captures_iter()`](Regex::captures_iter) method.
+    ///
+    /// # Example
+    ///
+    /// Find all words followed by an exclamation point:
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"\w+(?=!)").unwrap();
+    /// let mut matches = re.find_iter("so fancy! even with! iterators!");
+    /// assert_eq!(matches.next().unwrap().unwrap().as_str(), "fancy");
+    /// assert_eq!(matches.next().unwrap().unwrap().as_str(), "with");
+    /// assert_eq!(matches.next().unwrap().unwrap().as_str(), "iterators");
+    /// assert!(matches.next().is_none());
+    /// ```
+    pub fn find_iter<'r, 't>(&'r self, text: &'t str) -> Matches<'r, 't> {
+        Matches {
+            re: self,
+            text,
+            last_end: 0,
+            last_match: None,
+        }
+    }
+
     /// Returns the capture groups for the first match in `text`.
     ///
     /// If no match is found, then `Ok(None)` is returned.
@@ -451,14 +894,15 @@ impl Regex {
     /// assert_eq!(captures.get(0).unwrap().as_str(), "2018-04-07");
     /// ```
     pub fn captures<'t>(&self, text: &'t str) -> Result<Option<Captures<'t>>> {
-        match &self.0 {
-            RegexImpl::Wrap { inner, .. } => Ok(inner.captures(text).map(|caps| {
-                Captures(CapturesImpl::Wrap {
+        match &self.inner {
+            RegexImpl::Wrap { inner, .. } => Ok(inner.captures(text).map(|caps| Captures {
+                inner: CapturesImpl::Wrap {
                     text,
                     inner: caps,
                     offset: 0,
                     enclosing_groups: 0,
-                })
+                },
+                named_groups: self.named_groups.clone(),
             })),
             RegexImpl::Fancy {
                 prog,
@@ -466,15 +910,89 @@ impl Regex {
                 options,
                 ..
             } => {
-                let result = vm::run(prog, text, 0, 0, options)?;
+                let result = vm::run(prog, text.as_bytes(), 0, 0, options)?;
                 Ok(result.map(|mut saves| {
                     saves.truncate(n_groups * 2);
-                    Captures(CapturesImpl::Fancy { text, saves })
+                    Captures {
+                        inner: CapturesImpl::Fancy { text, saves },
+                        named_groups: self.named_groups.clone(),
+                    }
                 }))
             }
         }
     }
 
+    /// Returns an iterator over all the non-overlapping capture groups matched in `text`.
+    ///
+    /// # Examples
+    ///
+    /// Finding all dates and capturing their parts:
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"(\d{4})-(\d{2})-(\d{2})(?!\d)").unwrap();
+    /// let text = "Dates: 2018-04-07, 2019-12-09 and 2020-01-010";
+    /// let mut iter = re.captures_iter(text);
+    ///
+    /// let captures = iter.next().unwrap().unwrap();
+    /// assert_eq!(captures.get(1).unwrap().as_str(), "2018");
+    /// let captures = iter.next().unwrap().unwrap();
+    /// assert_eq!(captures.get(3).unwrap().as_str(), "09");
+    /// assert!(iter.next().is_none());
+    /// ```
+    pub fn captures_iter<'r, 't>(&'r self, text: &'t str) -> CaptureMatches<'r, 't> {
+        CaptureMatches(self.find_iter(text))
+    }
+
+    /// Returns an iterator of substrings of `text` delimited by a match of the regex. Namely,
+    /// each element of the iterator corresponds to text that *isn't* matched by the regex.
+    ///
+    /// The semantics are the same as for the regex crate's `split`, e.g. splitting an empty
+    /// string yields a single empty string. Errors from running the regex are returned as items
+    /// of the iterator, after which the iterator stops.
+    ///
+    /// # Example
+    ///
+    /// Split on commas that are not escaped with a backslash:
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"(?<!\\),").unwrap();
+    /// let fields: Vec<&str> = re.split(r"a,b\,c,d").map(|s| s.unwrap()).collect();
+    /// assert_eq!(fields, vec!["a", r"b\,c", "d"]);
+    /// ```
+    pub fn split<'r, 't>(&'r self, text: &'t str) -> Split<'r, 't> {
+        Split {
+            matches: self.find_iter(text),
+            last: 0,
+        }
+    }
+
+    /// Returns an iterator of at most `limit` substrings of `text` delimited by a match of the
+    /// regex. (A `limit` of `0` will return no substrings.) Namely, each element of the iterator
+    /// corresponds to text that *isn't* matched by the regex. The remainder of the string that
+    /// is not split will be the last element in the iterator.
+    ///
+    /// # Example
+    ///
+    /// Get the first two words:
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"\W+").unwrap();
+    /// let fields: Vec<&str> = re.splitn("Hey! How are you?", 3).map(|s| s.unwrap()).collect();
+    /// assert_eq!(fields, vec!["Hey", "How", "are you?"]);
+    /// ```
+    pub fn splitn<'r, 't>(&'r self, text: &'t str, limit: usize) -> SplitN<'r, 't> {
+        SplitN {
+            splits: self.split(text),
+            n: limit,
+        }
+    }
+
     /// Returns the capture groups for the first match in `text`, starting from
     /// the specified byte position `pos`.
     ///
@@ -510,51 +1028,203 @@ impl Regex {
     /// of the string slice.
     ///
     pub fn captures_from_pos<'t>(&self, text: &'t str, pos: usize) -> Result<Option<Captures<'t>>> {
-        match &self.0 {
+        match &self.inner {
             RegexImpl::Wrap { inner, inner1, .. } => {
                 if inner1.is_none() || pos == 0 {
                     let result = inner.captures(&text[pos..]);
-                    Ok(result.map(|caps| {
-                        Captures(CapturesImpl::Wrap {
+                    Ok(result.map(|caps| Captures {
+                        inner: CapturesImpl::Wrap {
                             text,
                             inner: caps,
                             offset: pos,
                             enclosing_groups: 0,
-                        })
+                        },
+                        named_groups: self.named_groups.clone(),
                     }))
                 } else {
-                    let ix = prev_codepoint_ix(text, pos);
+                    let ix = prev_codepoint_ix(text.as_bytes(), pos);
                     let inner1 = inner1.as_ref().unwrap();
                     let result = inner1.captures(&text[ix..]);
-                    Ok(result.map(|caps| {
-                        Captures(CapturesImpl::Wrap {
+                    Ok(result.map(|caps| Captures {
+                        inner: CapturesImpl::Wrap {
                             text,
                             inner: caps,
                             offset: ix,
                             enclosing_groups: 1,
-                        })
+                        },
+                        named_groups: self.named_groups.clone(),
                     }))
                 }
             }
+            RegexImpl::Fancy { .. } => Ok(self.captures_consumed(text, pos)?.map(|(caps, _)| caps)),
+        }
+    }
+
+    /// Like `captures_from_pos`, but also returns the start of the text consumed by the match,
+    /// see `Prog::consumed_start`.
+    fn captures_consumed<'t>(
+        &self,
+        text: &'t str,
+        pos: usize,
+    ) -> Result<Option<(Captures<'t>, usize)>> {
+        match &self.inner {
+            RegexImpl::Wrap { .. } => Ok(self.captures_from_pos(text, pos)?.map(|caps| {
+                let start = caps.get(0).unwrap().start();
+                (caps, start)
+            })),
             RegexImpl::Fancy {
                 prog,
                 n_groups,
                 options,
                 ..
             } => {
-                let result = vm::run(prog, text, pos, 0, options)?;
+                let result = vm::run(prog, text.as_bytes(), pos, 0, options)?;
                 Ok(result.map(|mut saves| {
+                    let consumed_start = prog.consumed_start(&saves);
                     saves.truncate(n_groups * 2);
-                    Captures(CapturesImpl::Fancy { text, saves })
+                    let captures = Captures {
+                        inner: CapturesImpl::Fancy { text, saves },
+                        named_groups: self.named_groups.clone(),
+                    };
+                    (captures, consumed_start)
                 }))
             }
         }
     }
 
+    /// Replaces the leftmost-first match with the replacement provided. The replacement can be a
+    /// regular string (where `$N` and `$name` are expanded to match capture groups) or a function
+    /// that takes the matches' `Captures` and returns the replaced string.
+    ///
+    /// If no match is found, then a copy of the string is returned unchanged.
+    ///
+    /// Returns an [`Error`](enum.Error.html) if running the regex fails, e.g. because the
+    /// backtrack limit was exceeded.
+    ///
+    /// # Replacement string syntax
+    ///
+    /// All instances of `$N` in the replacement text is replaced with the corresponding capture
+    /// group `N`. `N` may be an integer corresponding to the index of the capture group (counted by
+    /// order of opening parenthesis where `0` is the entire match) or it can be a name
+    /// (consisting of letters, digits or underscores) corresponding to a named capture group. If
+    /// `N` is not a valid capture group, then it is replaced with the empty string.
+    ///
+    /// The longest possible name is used, e.g. `$1a` looks up the capture group named `1a` and not
+    /// the capture group at index `1`. To exert more precise control over the name, use braces,
+    /// e.g. `${1}a`.
+    ///
+    /// To write a literal `$` use `$$`. If you don't want any expansion, use
+    /// [`NoExpand`](struct.NoExpand.html).
+    ///
+    /// # Examples
+    ///
+    /// Swap the words of all pairs of the same word:
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"(\w+)(?= \1)").unwrap();
+    /// let result = re.replace("say it it again", "[$1]").unwrap();
+    /// assert_eq!(result, "say [it] it again");
+    /// ```
+    ///
+    /// Using a closure:
+    ///
+    /// ```rust
+    /// # use fancy_regex::{Captures, Regex};
+    ///
+    /// let re = Regex::new(r"(?<=\$)(\d+)").unwrap();
+    /// let result = re
+    ///     .replace("costs $7", |caps: &Captures| {
+    ///         let n: u32 = caps.get(1).unwrap().as_str().parse().unwrap();
+    ///         (n * 2).to_string()
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(result, "costs $14");
+    /// ```
+    pub fn replace<'t, R: Replacer>(&self, text: &'t str, rep: R) -> Result<Cow<'t, str>> {
+        self.replacen(text, 1, rep)
+    }
+
+    /// Replaces all non-overlapping matches in `text` with the replacement provided. This is the
+    /// same as calling `replacen` with `limit` set to `0`.
+    ///
+    /// See the documentation for `replace` for details on how to access capturing group matches
+    /// in the replacement string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"(\w)\1").unwrap();
+    /// let result = re.replace_all("aabbcd ee", "<$1>").unwrap();
+    /// assert_eq!(result, "<a><b>cd <e>");
+    /// ```
+    pub fn replace_all<'t, R: Replacer>(&self, text: &'t str, rep: R) -> Result<Cow<'t, str>> {
+        self.replacen(text, 0, rep)
+    }
+
+    /// Replaces at most `limit` non-overlapping matches in `text` with the replacement provided.
+    /// If `limit` is 0, then all non-overlapping matches are replaced.
+    ///
+    /// See the documentation for `replace` for details on how to access capturing group matches
+    /// in the replacement string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fancy_regex::Regex;
+    ///
+    /// let re = Regex::new(r"(\w)\1").unwrap();
+    /// let result = re.replacen("aabbcd ee", 2, "$1").unwrap();
+    /// assert_eq!(result, "abcd ee");
+    /// ```
+    pub fn replacen<'t, R: Replacer>(
+        &self,
+        text: &'t str,
+        limit: usize,
+        mut rep: R,
+    ) -> Result<Cow<'t, str>> {
+        let mut new = String::new();
+        let mut last_match = 0;
+        let mut replaced = false;
+        // Stop before searching for the match after the last one, 0 means no limit
+        let limit = if limit == 0 { usize::MAX } else { limit };
+
+        // If we know that the replacement doesn't have any capture expansions, then we can use
+        // the faster `find_iter`.
+        if let Some(rep) = rep.no_expansion() {
+            for m in self.find_iter(text).take(limit) {
+                let m = m?;
+                new.push_str(&text[last_match..m.start()]);
+                new.push_str(&rep);
+                last_match = m.end();
+                replaced = true;
+            }
+        } else {
+            for caps in self.captures_iter(text).take(limit) {
+                let caps = caps?;
+                // unwrap on 0 is OK because captures only reports matches
+                let m = caps.get(0).unwrap();
+                new.push_str(&text[last_match..m.start()]);
+                rep.replace_append(&caps, &mut new);
+                last_match = m.end();
+                replaced = true;
+            }
+        }
+
+        if !replaced {
+            return Ok(Cow::Borrowed(text));
+        }
+        new.push_str(&text[last_match..]);
+        Ok(Cow::Owned(new))
+    }
+
     // for debugging only
     #[doc(hidden)]
     pub fn debug_print(&self) {
-        match &self.0 {
+        match &self.inner {
             RegexImpl::Wrap { inner, .. } => println!("wrapped {:?}", inner),
             RegexImpl::Fancy { prog, .. } => prog.debug_print(),
         }
@@ -585,13 +1255,152 @@ impl<'t> Match<'t> {
     }
 }
 
+impl<'r, 't> Iterator for Matches<'r, 't> {
+    type Item = Result<Match<'t>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.last_end > self.text.len() {
+            return None;
+        }
+
+        let (mat, consumed_start) = match self.re.find_consumed(self.text, self.last_end) {
+            Err(error) => {
+                // Stop after the error instead of running the same search again
+                self.last_end = self.text.len() + 1;
+                return Some(Err(error));
+            }
+            Ok(None) => return None,
+            Ok(Some(found)) => found,
+        };
+
+        // With `\K`, an empty match can still have consumed text before it, e.g. for `a\K`. Then
+        // the next search starts at its end, like after a non-empty match.
+        if consumed_start == mat.end {
+            // This is an empty match. To ensure we make progress, start the next search at the
+            // next codepoint.
+            self.last_end = next_codepoint_ix(self.text, mat.end);
+            // Don't accept empty matches immediately following a match, just move on to the next
+            // match (same as the regex crate).
+            if Some(mat.end) == self.last_match {
+                return self.next();
+            }
+        } else {
+            self.last_end = mat.end;
+        }
+
+        self.last_match = Some(mat.end);
+        Some(Ok(mat))
+    }
+}
+
+impl<'r, 't> Iterator for CaptureMatches<'r, 't> {
+    type Item = Result<Captures<'t>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let matches = &mut self.0;
+        if matches.last_end > matches.text.len() {
+            return None;
+        }
+
+        let (captures, consumed_start) =
+            match matches.re.captures_consumed(matches.text, matches.last_end) {
+                Err(error) => {
+                    // Same as in `Matches`, stop after the error
+                    matches.last_end = matches.text.len() + 1;
+                    return Some(Err(error));
+                }
+                Ok(None) => return None,
+                Ok(Some(found)) => found,
+            };
+        let mat = captures
+            .get(0)
+            .expect("captures always contain the whole match");
+
+        // Same empty match handling as in `Matches`
+        if consumed_start == mat.end {
+            matches.last_end = next_codepoint_ix(matches.text, mat.end);
+            if Some(mat.end) == matches.last_match {
+                return self.next();
+            }
+        } else {
+            matches.last_end = mat.end;
+        }
+
+        matches.last_match = Some(mat.end);
+        Some(Ok(captures))
+    }
+}
+
+impl<'r> Iterator for CaptureNames<'r> {
+    type Item = Option<&'r str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'r, 't> Iterator for Split<'r, 't> {
+    type Item = Result<&'t str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.matches.text;
+        if self.last > text.len() {
+            return None;
+        }
+        match self.matches.next() {
+            None => {
+                let s = &text[self.last..];
+                // Next call will return None
+                self.last = text.len() + 1;
+                Some(Ok(s))
+            }
+            Some(Err(error)) => {
+                self.last = text.len() + 1;
+                Some(Err(error))
+            }
+            Some(Ok(m)) => {
+                let s = &text[self.last..m.start()];
+                self.last = m.end();
+                Some(Ok(s))
+            }
+        }
+    }
+}
+
+impl<'r, 't> Iterator for SplitN<'r, 't> {
+    type Item = Result<&'t str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.n == 0 {
+            return None;
+        }
+        self.n -= 1;
+        if self.n > 0 {
+            return self.splits.next();
+        }
+        // The last element is the rest of the text
+        let text = self.splits.matches.text;
+        if self.splits.last > text.len() {
+            None
+        } else {
+            let s = &text[self.splits.last..];
+            self.splits.last = text.len() + 1;
+            Some(Ok(s))
+        }
+    }
+}
+
 impl<'t> Captures<'t> {
     /// Get the capture group by its index in the regex.
     ///
     /// If there is no match for that group or the index does not correspond to a group, `None` is
     /// returned. The index 0 returns the whole match.
     pub fn get(&self, i: usize) -> Option<Match<'t>> {
-        match &self.0 {
+        match &self.inner {
             CapturesImpl::Wrap {
                 text,
                 inner,
@@ -608,7 +1417,7 @@ impl<'t> Captures<'t> {
                     return None;
                 }
                 let lo = saves[slot];
-                if lo == std::usize::MAX {
+                if lo == usize::MAX {
                     return None;
                 }
                 let hi = saves[slot + 1];
@@ -621,6 +1430,28 @@ impl<'t> Captures<'t> {
         }
     }
 
+    /// Get the capture group by its name in the regex, e.g. `(?<name>...)`.
+    ///
+    /// If there is no match for that group or the regex doesn't have a group with that name,
+    /// `None` is returned. If multiple groups have the name, the last one of them that matched is
+    /// returned.
+    pub fn name(&self, name: &str) -> Option<Match<'t>> {
+        let groups = self.named_groups.get(name)?;
+        groups.iter().rev().find_map(|&i| self.get(i))
+    }
+
+    /// Expands all instances of `$group` in `replacement` to the corresponding capture group,
+    /// and writes them to the `dst` buffer given.
+    ///
+    /// `group` may be an integer corresponding to the index of the capture group (counted by
+    /// order of opening parenthesis where `0` is the entire match) or it can be a name
+    /// corresponding to a named capture group. If `group` is not a valid capture group, then it
+    /// is replaced with the empty string. See [`Regex::replace`](struct.Regex.html#method.replace)
+    /// for more details on the syntax.
+    pub fn expand(&self, replacement: &str, dst: &mut String) {
+        replacer::expand(self, replacement, dst);
+    }
+
     /// Iterate over the captured groups in order in which they appeared in the regex. The first
     /// capture corresponds to the whole match.
     pub fn iter<'c>(&'c self) -> SubCaptureMatches<'c, 't> {
@@ -628,8 +1459,9 @@ impl<'t> Captures<'t> {
     }
 
     /// How many groups were captured.
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        match self.0 {
+        match self.inner {
             CapturesImpl::Wrap {
                 ref inner,
                 enclosing_groups,
@@ -640,6 +1472,36 @@ impl<'t> Captures<'t> {
     }
 }
 
+/// Get a group by index.
+///
+/// # Panics
+///
+/// If there is no group at the given index.
+impl<'t> Index<usize> for Captures<'t> {
+    type Output = str;
+
+    fn index(&self, i: usize) -> &str {
+        self.get(i)
+            .map(|m| m.as_str())
+            .unwrap_or_else(|| panic!("no group at index '{}'", i))
+    }
+}
+
+/// Get a group by name.
+///
+/// # Panics
+///
+/// If there is no group named by the given value.
+impl<'t, 'i> Index<&'i str> for Captures<'t> {
+    type Output = str;
+
+    fn index(&self, name: &'i str) -> &str {
+        self.name(name)
+            .map(|m| m.as_str())
+            .unwrap_or_else(|| panic!("no group named '{}'", name))
+    }
+}
+
 impl<'c, 't> Iterator for SubCaptureMatches<'c, 't> {
     type Item = Option<Match<'t>>;
 
@@ -674,12 +1536,21 @@ pub enum Expr {
     StartLine,
     /// End of a line
     EndLine,
+    /// The position where the search started, i.e. the `pos` of `find_from_pos` or the end of the
+    /// previous match when iterating, regex `\G`
+    ContinueFromPreviousMatchEnd,
+    /// Reset the start of the reported match to the current position, regex `\K`, e.g. `foo\Kbar`
+    /// matches `bar` but only if it's preceded by `foo`
+    KeepOut,
     /// The string as a literal, e.g. `a`
     Literal {
         /// The string to match
         val: String,
         /// Whether match is case-insensitive or not
         casei: bool,
+        /// Whether case-insensitive matching uses Unicode case folding (e.g. `k` matches the
+        /// Kelvin sign `K`), or only ignores the case of ASCII letters (flag `u` off)
+        unicode: bool,
     },
     /// Concatenation of multiple expressions, must match in order, e.g. `a.` is a concatenation of
     /// the literal `a` and `.` for any character
@@ -687,9 +1558,20 @@ pub enum Expr {
     /// Alternative of multiple expressions, one of them must match, e.g. `a|b` is an alternative
     /// where either the literal `a` or `b` must match
     Alt(Vec<Expr>),
+    /// Branch reset group, e.g. `(?|(a)|(b))`. Like `Alt`, but the capture groups of each
+    /// alternative are numbered starting from the same number, so both `(a)` and `(b)` are group 1.
+    BranchReset(Vec<Expr>),
     /// Capturing group of expression, e.g. `(a.)` matches `a` and any character and "captures"
     /// (remembers) the match
     Group(Box<Expr>),
+    /// Named capturing group of expression, e.g. `(?<name>a.)` or `(?P<name>a.)`. Like `Group`,
+    /// but the captured group can also be accessed by its name.
+    NamedGroup {
+        /// The name of the group
+        name: String,
+        /// The expression of the group
+        child: Box<Expr>,
+    },
     /// Look-around (e.g. positive/negative look-ahead or look-behind) with an expression, e.g.
     /// `(?=a)` means the next character must be `a` (but the match is not consumed)
     LookAround(Box<Expr>, LookAround),
@@ -717,10 +1599,63 @@ pub enum Expr {
     },
     /// Back reference to a capture group, e.g. `\1` in `(abc|def)\1` references the captured group
     /// and the whole regex matches either `abcabc` or `defdef`.
-    Backref(usize),
+    Backref {
+        /// The number of the referenced group
+        group: usize,
+        /// Whether the comparison with the captured text is case-insensitive or not
+        casei: bool,
+        /// Whether the case-insensitive comparison uses Unicode case folding or only ignores the
+        /// case of ASCII letters
+        unicode: bool,
+    },
+    /// Back reference to a name that multiple capture groups have, e.g. `\k<x>` in
+    /// `(?:(?<x>a)|(?<x>b))\k<x>`. The groups are tried from the last one to the first, the first
+    /// one that has matched and whose captured text matches is used.
+    MultiBackref {
+        /// The numbers of the groups with the name, in the order they are defined
+        groups: Vec<usize>,
+        /// Whether the comparison with the captured text is case-insensitive or not
+        casei: bool,
+        /// Whether the case-insensitive comparison uses Unicode case folding or only ignores the
+        /// case of ASCII letters
+        unicode: bool,
+    },
     /// Atomic non-capturing group, e.g. `(?>ab|a)` in text that contains `ab` will match `ab` and
     /// never backtrack and try `a`, even if matching fails after the atomic group.
     AtomicGroup(Box<Expr>),
+    /// Subroutine call to the group with the given number, e.g. `\g<1>` or `(?1)`, which matches
+    /// the group's expression (which can contain calls itself, i.e. recursion). Group 0 is the
+    /// whole regex, e.g. `(?R)`.
+    SubroutineCall(usize),
+    /// Condition that the group with the given number has matched, e.g. the `(1)` in
+    /// `(?(1)yes|no)`
+    BackrefExistsCondition(usize),
+    /// Conditional, e.g. `(?(1)yes|no)` or `(?(?=a)yes|no)`. If the condition matches, the true
+    /// branch has to match after it, otherwise the false branch has to match instead. A missing
+    /// branch is `Empty`.
+    Conditional {
+        /// The condition, either a `BackrefExistsCondition` or a look-around assertion
+        condition: Box<Expr>,
+        /// The expression to match if the condition matches
+        true_branch: Box<Expr>,
+        /// The expression to match if the condition doesn't match
+        false_branch: Box<Expr>,
+    },
+    /// Absent expression, e.g. `(?~|abc|\d+)`, which matches the child expression, but only in
+    /// the text before the first match of the absent expression (searching from the current
+    /// position). The absent repeater `(?~abc)` has `\O*` as the child, so it matches the longest
+    /// text that doesn't contain `abc`, e.g. the ` ` in `/\*(?~\*/)\*/` on `/* */ */`.
+    Absent {
+        /// The expression that the match must not reach
+        absent: Box<Expr>,
+        /// The expression to match
+        child: Box<Expr>,
+    },
+    /// Absent stopper, e.g. `(?~|abc)`, which limits the rest of the regex to the text before the
+    /// first match of the expression
+    AbsentStopper(Box<Expr>),
+    /// Range clear `(?~|)`, which removes the limit of absent stoppers
+    AbsentRangeClear,
 }
 
 /// Type of look-around assertion as used for a look-around expression.
@@ -774,13 +1709,17 @@ impl Expr {
         match *self {
             Expr::Empty => (),
             Expr::Any { newline } => buf.push_str(if newline { "(?s:.)" } else { "." }),
-            Expr::Literal { ref val, casei } => {
+            Expr::Literal {
+                ref val,
+                casei,
+                unicode,
+            } => {
                 if casei {
-                    buf.push_str("(?i:");
+                    buf.push_str(if unicode { "(?i:" } else { "(?i-u:" });
                 }
                 push_quoted(buf, val);
                 if casei {
-                    buf.push_str(")");
+                    buf.push(')');
                 }
             }
             Expr::StartText => buf.push('^'),
@@ -798,15 +1737,12 @@ impl Expr {
                     buf.push(')')
                 }
             }
-            Expr::Alt(ref children) => {
+            Expr::Alt(ref children) | Expr::BranchReset(ref children) => {
                 if precedence > 0 {
                     buf.push_str("(?:");
                 }
 
-                let is_empty = |e: &Expr| match e {
-                    Expr::Empty => true,
-                    _ => false,
-                };
+                let is_empty = |e: &Expr| matches!(e, Expr::Empty);
                 let contains_empty = children.iter().any(&is_empty);
                 if contains_empty {
                     buf.push_str("(?:");
@@ -831,6 +1767,16 @@ impl Expr {
                 child.to_str(buf, 0);
                 buf.push(')');
             }
+            Expr::NamedGroup {
+                ref name,
+                ref child,
+            } => {
+                buf.push_str("(?P<");
+                buf.push_str(name);
+                buf.push('>');
+                child.to_str(buf, 0);
+                buf.push(')');
+            }
             Expr::Repeat {
                 ref child,
                 lo,
@@ -873,7 +1819,7 @@ impl Expr {
                 }
                 buf.push_str(inner);
                 if casei {
-                    buf.push_str(")");
+                    buf.push(')');
                 }
             }
             _ => panic!("attempting to format hard expr"),
@@ -882,18 +1828,26 @@ impl Expr {
 }
 
 // precondition: ix > 0
-fn prev_codepoint_ix(s: &str, mut ix: usize) -> usize {
-    let bytes = s.as_bytes();
+fn prev_codepoint_ix(s: &[u8], mut ix: usize) -> usize {
     loop {
         ix -= 1;
         // fancy bit magic for ranges 0..0x80 + 0xc0..
-        if (bytes[ix] as i8) >= -0x40 {
+        if ix == 0 || (s[ix] as i8) >= -0x40 {
             break;
         }
     }
     ix
 }
 
+// Returns the index after the codepoint at `ix`, or `ix + 1` at the end of the string.
+fn next_codepoint_ix(s: &str, ix: usize) -> usize {
+    if ix < s.len() {
+        ix + codepoint_len(s.as_bytes()[ix])
+    } else {
+        ix + 1
+    }
+}
+
 fn codepoint_len(b: u8) -> usize {
     match b {
         b if b < 0x80 => 1,
@@ -948,7 +1902,6 @@ mod tests {
     use crate::parse::make_literal;
     use crate::Expr;
     use crate::Regex;
-    use std::usize;
     //use detect_possible_backref;
 
     // tests for to_str
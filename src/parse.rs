@@ -22,8 +22,8 @@
 
 use bit_set::BitSet;
 use regex::escape;
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::usize;
 
 use crate::codepoint_len;
 use crate::Error;
@@ -32,41 +32,130 @@ use crate::LookAround::*;
 use crate::Result;
 use crate::MAX_RECURSION;
 
-const FLAG_CASEI: u32 = (1 << 0);
-const FLAG_MULTI: u32 = (1 << 1);
-const FLAG_DOTNL: u32 = (1 << 2);
-const FLAG_SWAP_GREED: u32 = (1 << 3);
-const FLAG_IGNORE_SPACE: u32 = (1 << 4);
-const FLAG_UNICODE: u32 = (1 << 5);
+pub(crate) const FLAG_CASEI: u32 = 1 << 0;
+pub(crate) const FLAG_MULTI: u32 = 1 << 1;
+pub(crate) const FLAG_DOTNL: u32 = 1 << 2;
+pub(crate) const FLAG_SWAP_GREED: u32 = 1 << 3;
+pub(crate) const FLAG_IGNORE_SPACE: u32 = 1 << 4;
+pub(crate) const FLAG_UNICODE: u32 = 1 << 5;
+
+// Escapes that are implemented using other expressions. They are atomic like in Oniguruma and PCRE,
+// e.g. `\R\n` doesn't match `\r\n`.
+
+/// `\Z`: end of text, or before a newline at the end of text
+const END_TEXT_IGNORE_TRAILING_NEWLINE: &str = r"(?=\n?\z)";
+/// `\R`: any line break, with `\r\n` matched as one
+const LINE_BREAK: &str = r"(?>\r\n|[\n\x0B\x0C\r\x{85}\x{2028}\x{2029}])";
+/// `\R` with Unicode mode disabled
+const LINE_BREAK_ASCII: &str = r"(?>\r\n|[\n\x0B\x0C\r])";
+/// `\X`: extended grapheme cluster, following the regex in Unicode Standard Annex #29
+const GRAPHEME_CLUSTER: &str = concat!(
+    r"(?>\r\n|\p{gcb=Control}|\p{gcb=CR}|\p{gcb=LF}|",
+    r"\p{gcb=Prepend}*",
+    r"(?:",
+    // Hangul syllable
+    r"\p{gcb=L}*(?:\p{gcb=V}+|\p{gcb=LV}\p{gcb=V}*|\p{gcb=LVT})\p{gcb=T}*|\p{gcb=L}+|\p{gcb=T}+|",
+    // Regional indicator pair (flag)
+    r"\p{gcb=RI}\p{gcb=RI}|",
+    // Emoji sequence
+    r"\p{Extended_Pictographic}(?:\p{gcb=Extend}*\p{gcb=ZWJ}\p{Extended_Pictographic})*|",
+    r"[^\p{gcb=Control}\p{gcb=CR}\p{gcb=LF}]",
+    r")",
+    r"[\p{gcb=Extend}\p{gcb=ZWJ}\p{gcb=SpacingMark}]*)"
+);
+/// `\X` with Unicode mode disabled, any byte or `\r\n`
+const GRAPHEME_CLUSTER_BYTES: &str = r"(?>\r\n|(?s-u:.))";
 
 #[derive(Debug)]
 pub(crate) struct Parser<'a> {
     re: &'a str, // source
     backrefs: BitSet,
     flags: u32,
+    /// Number of capture groups opened so far (group 0 is the whole match)
+    curr_group: usize,
+    /// The groups with each name, multiple groups can have the same name
+    named_groups: HashMap<String, Vec<usize>>,
+    /// Number of look-around assertions the parser is currently inside of
+    lookaround_depth: usize,
+    /// All named groups of the regex, for subroutine calls to groups that are defined later
+    /// (`None` if not known yet)
+    all_named_groups: Option<HashMap<String, Vec<usize>>>,
+    /// Whether there is a subroutine call to a name that isn't known yet
+    unresolved_call: bool,
+    /// Number of capture groups in the whole regex, for number escapes like `\12` that are only
+    /// backrefs if there are that many groups (`None` if not known yet)
+    all_groups: Option<usize>,
+    /// Whether there is a number escape that depends on the number of groups
+    unresolved_number: bool,
+    /// The highest group number that is the target of a subroutine call
+    max_called_group: usize,
+    /// Whether the regex has to match valid UTF-8 only, i.e. `(?-u)` is not allowed
+    utf8: bool,
 }
 
 impl<'a> Parser<'a> {
     /// Parse the regex and return an expression (AST) and a bit set with the indexes of groups
     /// that are referenced by backrefs.
     pub(crate) fn parse(re: &str) -> Result<(Expr, BitSet)> {
-        let mut p = Parser::new(re);
-        let (ix, result) = p.parse_re(0, 0)?;
+        Parser::parse_with_flags(re, 0)
+    }
+
+    /// Like `parse`, but with the given flags (e.g. `FLAG_CASEI`) initially set, as if the regex
+    /// started with the corresponding inline flags.
+    pub(crate) fn parse_with_flags(re: &str, flags: u32) -> Result<(Expr, BitSet)> {
+        Parser::parse_impl(re, flags, true)
+    }
+
+    /// Like `parse_with_flags`, but the regex is used for matching on bytes, so Unicode mode can be
+    /// disabled using `(?-u)`.
+    pub(crate) fn parse_bytes_with_flags(re: &str, flags: u32) -> Result<(Expr, BitSet)> {
+        Parser::parse_impl(re, flags, false)
+    }
+
+    fn parse_impl(re: &str, flags: u32, utf8: bool) -> Result<(Expr, BitSet)> {
+        let mut p = Parser::new(re, flags, utf8);
+        let (mut ix, mut result) = p.parse_re(0, 0)?;
+        if p.unresolved_call || p.unresolved_number {
+            // A subroutine call refers to a group by a name that is only defined later, or a
+            // number escape depends on how many groups there are, parse again now that all the
+            // groups are known.
+            let all_named_groups = p.named_groups;
+            let all_groups = p.curr_group;
+            p = Parser::new(re, flags, utf8);
+            p.all_named_groups = Some(all_named_groups);
+            p.all_groups = Some(all_groups);
+            (ix, result) = p.parse_re(0, 0)?;
+        }
         if ix < re.len() {
             return Err(Error::ParseError);
         }
+        if p.max_called_group > p.curr_group {
+            return Err(Error::InvalidSubroutineCall);
+        }
         Ok((result, p.backrefs))
     }
 
-    fn new(re: &str) -> Parser<'_> {
+    fn new(re: &str, flags: u32, utf8: bool) -> Parser<'_> {
         Parser {
             re,
             backrefs: BitSet::new(),
-            flags: FLAG_UNICODE,
+            flags: FLAG_UNICODE | flags,
+            curr_group: 0,
+            named_groups: HashMap::new(),
+            lookaround_depth: 0,
+            all_named_groups: None,
+            unresolved_call: false,
+            all_groups: None,
+            unresolved_number: false,
+            max_called_group: 0,
+            utf8,
         }
     }
 
+    // Flags that are changed by `(?i)` etc. apply until the end of the enclosing group, including
+    // the following alternatives, so they are restored at the end.
     fn parse_re(&mut self, ix: usize, depth: usize) -> Result<(usize, Expr)> {
+        let flags = self.flags;
         let (ix, child) = self.parse_branch(ix, depth)?;
         let mut ix = self.optional_whitespace(ix);
         if self.re[ix..].starts_with('|') {
@@ -77,8 +166,10 @@ impl<'a> Parser<'a> {
                 children.push(child);
                 ix = self.optional_whitespace(next);
             }
+            self.flags = flags;
             return Ok((ix, Expr::Alt(children)));
         }
+        self.flags = flags;
         Ok((ix, child))
     }
 
@@ -86,6 +177,19 @@ impl<'a> Parser<'a> {
         let mut children = Vec::new();
         let mut ix = ix;
         while ix < self.re.len() {
+            ix = self.optional_whitespace(ix);
+            if self.re[ix..].starts_with("\\Q") {
+                // quoted text, a repeat only applies to the last char, e.g. `\Qab\E+`
+                let (next, mut literals) = self.parse_quote(ix + 2);
+                ix = next;
+                if let Some(last) = literals.pop() {
+                    children.extend(literals);
+                    let (next, last) = self.parse_repeat_suffix(ix, last)?;
+                    children.push(last);
+                    ix = next;
+                }
+                continue;
+            }
             let (next, child) = self.parse_piece(ix, depth)?;
             if next == ix {
                 break;
@@ -104,6 +208,11 @@ impl<'a> Parser<'a> {
 
     fn parse_piece(&mut self, ix: usize, depth: usize) -> Result<(usize, Expr)> {
         let (ix, child) = self.parse_atom(ix, depth)?;
+        self.parse_repeat_suffix(ix, child)
+    }
+
+    // ix points after the expression that can be repeated, e.g. to `*` or `{2,3}`
+    fn parse_repeat_suffix(&self, ix: usize, child: Expr) -> Result<(usize, Expr)> {
         let mut ix = self.optional_whitespace(ix);
         if ix < self.re.len() {
             // fail when child is empty?
@@ -197,9 +306,9 @@ impl<'a> Parser<'a> {
         match self.re.as_bytes()[ix] {
             b'.' => Ok((
                 ix + 1,
-                Expr::Any {
+                self.non_unicode(Expr::Any {
                     newline: self.flag(FLAG_DOTNL),
-                },
+                }),
             )),
             b'^' => Ok((
                 ix + 1,
@@ -218,15 +327,24 @@ impl<'a> Parser<'a> {
                 },
             )),
             b'(' => self.parse_group(ix, depth),
+            b'\\' if self.re[ix + 1..].starts_with("g<") => {
+                // subroutine call in Oniguruma syntax, e.g. `\g<1>`, `\g<-1>` or `\g<name>`
+                self.parse_subroutine_call(ix + 3, b'>')
+            }
+            b'\\' if self.re[ix + 1..].starts_with("g'") => {
+                // subroutine call in Oniguruma syntax with quotes, e.g. `\g'1'` or `\g'name'`
+                self.parse_subroutine_call(ix + 3, b'\'')
+            }
             b'\\' => {
-                let (next, expr) = self.parse_escape(ix)?;
-                if let Expr::Backref(group) = expr {
-                    self.backrefs.insert(group);
-                }
-                Ok((next, expr))
+                let (next, expr) = self.parse_escape(ix, false)?;
+                self.insert_backrefs(&expr);
+                Ok((next, self.non_unicode(expr)))
             }
             b'+' | b'*' | b'?' | b'|' | b')' => Ok((ix, Expr::Empty)),
-            b'[' => self.parse_class(ix),
+            b'[' => {
+                let (next, expr) = self.parse_class(ix)?;
+                Ok((next, self.non_unicode(expr)))
+            }
             b => {
                 // TODO: maybe want to match multiple codepoints?
                 let next = ix + codepoint_len(b);
@@ -235,6 +353,7 @@ impl<'a> Parser<'a> {
                     Expr::Literal {
                         val: String::from(&self.re[ix..next]),
                         casei: self.flag(FLAG_CASEI),
+                        unicode: self.flag(FLAG_UNICODE),
                     },
                 ))
             }
@@ -242,7 +361,7 @@ impl<'a> Parser<'a> {
     }
 
     // ix points to \ character
-    fn parse_escape(&self, ix: usize) -> Result<(usize, Expr)> {
+    fn parse_escape(&mut self, ix: usize, in_class: bool) -> Result<(usize, Expr)> {
         if ix + 1 == self.re.len() {
             return Err(Error::TrailingBackslash);
         }
@@ -250,14 +369,92 @@ impl<'a> Parser<'a> {
         let b = bytes[ix + 1];
         let mut end = ix + 1 + codepoint_len(b);
         let mut size = 1;
-        if is_digit(b) {
+        if b == b'0' {
+            // octal with a leading zero, e.g. `\0` or `\012`
+            let (end, codepoint) = parse_octal(self.re, end, 2);
+            return self.codepoint(end, codepoint);
+        } else if is_digit(b) {
             if let Some((end, group)) = parse_decimal(self.re, ix + 1) {
-                // protect BitSet against unreasonably large value
-                if group < self.re.len() / 2 {
-                    return Ok((end, Expr::Backref(group)));
+                // A number with several digits is octal instead if the regex doesn't have that
+                // many groups, e.g. `\12` without 12 groups. In a class, it's always octal.
+                let octal = if in_class {
+                    true
+                } else if group < 10 {
+                    false
+                } else if let Some(all_groups) = self.all_groups {
+                    group > all_groups
+                } else {
+                    // resolved when parsing again
+                    self.unresolved_number = true;
+                    false
+                };
+                if !(octal && is_octal_digit(b)) && group < self.re.len() / 2 {
+                    // protect BitSet against unreasonably large value (above)
+                    return Ok((end, self.backref(group)));
                 }
             }
+            if is_octal_digit(b) {
+                let (end, codepoint) = parse_octal(self.re, ix + 1, 3);
+                return self.codepoint(end, codepoint);
+            }
             return Err(Error::InvalidBackref);
+        } else if b == b'o' && bytes.get(end) == Some(&b'{') {
+            // octal with braces, e.g. `\o{101}`
+            return match parse_braced_number(self.re, end + 1, 8) {
+                Some((end, codepoint)) => self.codepoint(end, codepoint),
+                None => Err(Error::InvalidEscape),
+            };
+        } else if b == b'c' || b == b'C' || b == b'M' {
+            // control or meta character, e.g. `\ca`, `\C-a`, `\M-a` or `\M-\C-a`
+            let (end, codepoint) = self.parse_control_or_meta(ix, false)?;
+            return self.codepoint(end, codepoint);
+        } else if b == b'k' {
+            // named or relative backref, e.g. `\k<name>`, `\k'name'`, `\k{name}` or `\k<-1>`
+            let close = match bytes.get(end) {
+                Some(b'<') => b'>',
+                Some(b'\'') => b'\'',
+                Some(b'{') => b'}',
+                _ => return Err(Error::InvalidBackref),
+            };
+            return self.parse_backref_ref(end + 1, close);
+        } else if b == b'g' && bytes.get(end) == Some(&b'{') {
+            // backref in PCRE syntax, e.g. `\g{1}`, `\g{-1}` or `\g{name}`
+            return self.parse_backref_ref(end + 1, b'}');
+        } else if b == b'g' && bytes.get(end).is_some_and(|&b| b == b'-' || is_digit(b)) {
+            // backref in PCRE syntax without braces, e.g. `\g1` or `\g-1`
+            let start = if bytes[end] == b'-' { end + 1 } else { end };
+            match parse_decimal(self.re, start) {
+                Some((next, _)) => {
+                    let group = self.resolve_backref(&self.re[end..next])?;
+                    return Ok((next, self.backref(group)));
+                }
+                None => return Err(Error::InvalidBackref),
+            }
+        } else if b == b'G' {
+            return Ok((end, Expr::ContinueFromPreviousMatchEnd));
+        } else if b == b'K' {
+            if self.lookaround_depth > 0 {
+                return Err(Error::KeepOutInLookAround);
+            }
+            return Ok((end, Expr::KeepOut));
+        } else if b == b'Z' {
+            return Ok((end, self.parse_builtin(END_TEXT_IGNORE_TRAILING_NEWLINE)?));
+        } else if b == b'R' {
+            let re = if self.flag(FLAG_UNICODE) {
+                LINE_BREAK
+            } else {
+                LINE_BREAK_ASCII
+            };
+            return Ok((end, self.parse_builtin(re)?));
+        } else if b == b'X' {
+            let re = if self.flag(FLAG_UNICODE) {
+                GRAPHEME_CLUSTER
+            } else {
+                GRAPHEME_CLUSTER_BYTES
+            };
+            return Ok((end, self.parse_builtin(re)?));
+        } else if b == b'N' || b == b'O' {
+            return Ok((end, Expr::Any { newline: b == b'O' }));
         } else if b == b'A' || b == b'z' || b == b'b' || b == b'B' {
             size = 0;
         } else if (b | 32) == b'd'
@@ -320,7 +517,7 @@ impl<'a> Parser<'a> {
             }
         } else if b'a' <= (b | 32) && (b | 32) <= b'z' {
             return Err(Error::InvalidEscape);
-        } else if 0x20 <= b && b <= 0x7f {
+        } else if (0x20..=0x7f).contains(&b) {
             // printable ASCII (including space, see issue #29)
             return Ok((end, make_literal(&self.re[ix + 1..end])));
         }
@@ -336,6 +533,87 @@ impl<'a> Parser<'a> {
         ))
     }
 
+    // ix points after the opening delimiter of a backref, e.g. after `\k<`
+    fn parse_backref_ref(&self, ix: usize, close: u8) -> Result<(usize, Expr)> {
+        let end = match self.re.as_bytes()[ix..].iter().position(|&b| b == close) {
+            Some(len) => ix + len,
+            None => return Err(Error::InvalidBackref),
+        };
+        let reference = &self.re[ix..end];
+        let expr = match self.named_groups.get(reference) {
+            Some(groups) if groups.len() > 1 => Expr::MultiBackref {
+                groups: groups.clone(),
+                casei: self.flag(FLAG_CASEI),
+                unicode: self.flag(FLAG_UNICODE),
+            },
+            _ => self.backref(self.resolve_backref(reference)?),
+        };
+        Ok((end + 1, expr))
+    }
+
+    // Remember the groups that are referenced by a backref expression
+    fn insert_backrefs(&mut self, expr: &Expr) {
+        match *expr {
+            Expr::Backref { group, .. } => {
+                self.backrefs.insert(group);
+            }
+            Expr::MultiBackref { ref groups, .. } => self.backrefs.extend(groups.iter().copied()),
+            _ => {}
+        }
+    }
+
+    // Resolve a backref to a group number. The reference can be a group number, a relative group
+    // number counting backwards from the last opened group (`-1` is the last opened group) or the
+    // name of a group that was defined before (the last one if multiple groups have the name).
+    fn resolve_backref(&self, s: &str) -> Result<usize> {
+        if let Some(digits) = s.strip_prefix('-') {
+            match usize::from_str(digits) {
+                Ok(relative) if relative >= 1 && relative <= self.curr_group => {
+                    Ok(self.curr_group + 1 - relative)
+                }
+                _ => Err(Error::InvalidBackref),
+            }
+        } else if !s.is_empty() && s.bytes().all(is_digit) {
+            match usize::from_str(s) {
+                // group 0 is the whole match, which can't be referenced from inside itself; also
+                // protect BitSet against unreasonably large value
+                Ok(group) if group >= 1 && group < self.re.len() / 2 => Ok(group),
+                _ => Err(Error::InvalidBackref),
+            }
+        } else {
+            match self.named_groups.get(s).and_then(|groups| groups.last()) {
+                Some(&group) => Ok(group),
+                None => Err(Error::InvalidBackref),
+            }
+        }
+    }
+
+    // If Unicode mode is disabled, make a delegate only match ASCII or bytes, e.g. `\w` or `[^a]`,
+    // and `.` match any byte instead of any character
+    fn non_unicode(&self, expr: Expr) -> Expr {
+        if self.flag(FLAG_UNICODE) {
+            return expr;
+        }
+        match expr {
+            Expr::Delegate { inner, size, casei } => Expr::Delegate {
+                inner: format!("(?-u:{})", inner),
+                size,
+                casei,
+            },
+            Expr::Any { newline } => Expr::Delegate {
+                inner: String::from(if newline { "(?s-u:.)" } else { "(?-u:.)" }),
+                size: 1,
+                casei: false,
+            },
+            expr => expr,
+        }
+    }
+
+    // Parse the regex that implements an escape in terms of other expressions, e.g. `\R`
+    fn parse_builtin(&self, re: &str) -> Result<Expr> {
+        Parser::parse_impl(re, 0, self.utf8).map(|(expr, _)| expr)
+    }
+
     // ix points after '\x', eg to 'A0' or '{12345}'
     fn parse_hex(&self, ix: usize) -> Result<(usize, Expr)> {
         if ix + 2 > self.re.len() {
@@ -343,31 +621,65 @@ impl<'a> Parser<'a> {
         }
         let bytes = self.re.as_bytes();
         let b = bytes[ix];
-        let (end, s) = if is_hex_digit(b) && is_hex_digit(bytes[ix + 1]) {
+        let (end, codepoint) = if is_hex_digit(b) && is_hex_digit(bytes[ix + 1]) {
             let end = ix + 2;
-            (end, &self.re[ix..end])
+            (end, u32::from_str_radix(&self.re[ix..end], 16).unwrap())
         } else if b == b'{' {
-            let starthex = ix + 1;
-            let mut endhex = starthex;
-            loop {
-                if endhex == self.re.len() {
-                    return Err(Error::InvalidHex);
-                }
-                let b = bytes[endhex];
-                if endhex > starthex && b == b'}' {
-                    break;
-                }
-                if is_hex_digit(b) && endhex < starthex + 8 {
-                    endhex += 1;
-                } else {
-                    return Err(Error::InvalidHex);
-                }
-            }
-            (endhex + 1, &self.re[starthex..endhex])
+            parse_braced_number(self.re, ix + 1, 16).ok_or(Error::InvalidHex)?
         } else {
             return Err(Error::InvalidHex);
         };
-        let codepoint = u32::from_str_radix(s, 16).unwrap();
+        self.codepoint(end, codepoint)
+    }
+
+    // ix points to the \ of `\c`, `\C-` or `\M-`. The value of a control character is the lower 5
+    // bits of the character (or 0x7F for `?`), a meta character has the high bit (0x80) set. They
+    // can be combined once, e.g. `\M-\C-a`.
+    fn parse_control_or_meta(&self, ix: usize, nested: bool) -> Result<(usize, u32)> {
+        let rest = &self.re[ix..];
+        let (start, meta) = if rest.starts_with("\\c") {
+            (ix + 2, false)
+        } else if rest.starts_with("\\C-") {
+            (ix + 3, false)
+        } else if rest.starts_with("\\M-") {
+            (ix + 3, true)
+        } else {
+            return Err(Error::InvalidEscape);
+        };
+        let (end, c) = match self.re.as_bytes().get(start) {
+            None => return Err(Error::InvalidEscape),
+            Some(b'\\') if self.re[start..].starts_with("\\\\") => (start + 2, u32::from(b'\\')),
+            Some(b'\\') if !nested => self.parse_control_or_meta(start, true)?,
+            Some(&b) if b.is_ascii() && b != b'\\' => (start + 1, u32::from(b)),
+            Some(_) => return Err(Error::InvalidEscape),
+        };
+        let codepoint = if meta {
+            c | 0x80
+        } else if c == u32::from(b'?') {
+            0x7F
+        } else {
+            c & 0x9F
+        };
+        Ok((end, codepoint))
+    }
+
+    // A literal for a codepoint from an escape, e.g. `\x41`, or a byte if Unicode mode is disabled
+    fn codepoint(&self, end: usize, codepoint: u32) -> Result<(usize, Expr)> {
+        if !self.flag(FLAG_UNICODE) && codepoint >= 0x80 {
+            // a byte instead of a char, only valid when matching on bytes
+            return if codepoint <= 0xff {
+                Ok((
+                    end,
+                    Expr::Delegate {
+                        inner: format!("\\x{:02X}", codepoint),
+                        size: 1,
+                        casei: self.flag(FLAG_CASEI),
+                    },
+                ))
+            } else {
+                Err(Error::InvalidCodepointValue)
+            };
+        }
         if let Some(c) = ::std::char::from_u32(codepoint) {
             let mut inner = String::with_capacity(4);
             inner.push(c);
@@ -376,6 +688,7 @@ impl<'a> Parser<'a> {
                 Expr::Literal {
                     val: inner,
                     casei: self.flag(FLAG_CASEI),
+                    unicode: self.flag(FLAG_UNICODE),
                 },
             ))
         } else {
@@ -383,7 +696,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_class(&self, ix: usize) -> Result<(usize, Expr)> {
+    fn parse_class(&mut self, ix: usize) -> Result<(usize, Expr)> {
         let bytes = self.re.as_bytes();
         let mut ix = ix + 1; // skip opening '['
         let mut class = String::new();
@@ -407,13 +720,19 @@ impl<'a> Parser<'a> {
                 return Err(Error::InvalidClass);
             }
             let end = match bytes[ix] {
+                b'\\' if bytes.get(ix + 1) == Some(&b'Q') => {
+                    // quoted text, e.g. `[\Q]\E]`
+                    let (next, quoted) = self.quoted_text(ix + 2);
+                    class.push_str(&escape(quoted));
+                    next
+                }
                 b'\\' => {
                     if ix + 1 == self.re.len() {
                         return Err(Error::InvalidClass);
                     }
 
                     // We support more escapes than regex, so parse it ourselves before delegating.
-                    let (end, expr) = self.parse_escape(ix)?;
+                    let (end, expr) = self.parse_escape(ix, true)?;
                     match expr {
                         Expr::Literal { val, .. } => {
                             class.push_str(&escape(&val));
@@ -460,12 +779,58 @@ impl<'a> Parser<'a> {
         ))
     }
 
+    fn backref(&self, group: usize) -> Expr {
+        Expr::Backref {
+            group,
+            casei: self.flag(FLAG_CASEI),
+            unicode: self.flag(FLAG_UNICODE),
+        }
+    }
+
     fn parse_group(&mut self, ix: usize, depth: usize) -> Result<(usize, Expr)> {
         let depth = depth + 1;
         if depth >= MAX_RECURSION {
             return Err(Error::RecursionExceeded);
         }
         let ix = self.optional_whitespace(ix + 1);
+        if self.re[ix..].starts_with("?P=") {
+            // named backref in Python syntax, e.g. `(?P=name)`
+            let (next, expr) = self.parse_backref_ref(ix + 3, b')')?;
+            self.insert_backrefs(&expr);
+            return Ok((next, expr));
+        }
+        if self.re[ix..].starts_with("?|") {
+            return self.parse_branch_reset(ix + 2, depth);
+        }
+        if self.re[ix..].starts_with("?(") {
+            return self.parse_conditional(ix + 2, depth);
+        }
+        if self.re[ix..].starts_with("?#") {
+            return self.parse_comment(ix + 2);
+        }
+        if self.re[ix..].starts_with("?~") {
+            return self.parse_absent(ix + 2, depth);
+        }
+        if self.re[ix..].starts_with("?R)") {
+            // recursion of the whole regex
+            return self.parse_subroutine_call(ix + 1, b')');
+        } else if self.re[ix..].starts_with("?&") {
+            // subroutine call in Perl syntax, e.g. `(?&name)`
+            return self.parse_subroutine_call(ix + 2, b')');
+        } else if self.re[ix..].starts_with("?P>") {
+            // subroutine call in Python syntax, e.g. `(?P>name)`
+            return self.parse_subroutine_call(ix + 3, b')');
+        }
+        let number = self.re[ix..]
+            .strip_prefix('?')
+            .map(|s| s.strip_prefix(['+', '-']).unwrap_or(s));
+        if number.is_some_and(|s| s.starts_with(|c: char| c.is_ascii_digit())) {
+            // subroutine call by number in PCRE syntax, e.g. `(?1)`, `(?-1)` or `(?+1)`
+            return self.parse_subroutine_call(ix + 1, b')');
+        }
+        if let Some((ix, name)) = self.parse_group_name(ix)? {
+            return self.parse_named_group(ix, name, depth);
+        }
         let (la, skip) = if self.re[ix..].starts_with("?=") {
             (Some(LookAhead), 2)
         } else if self.re[ix..].starts_with("?!") {
@@ -482,7 +847,16 @@ impl<'a> Parser<'a> {
             (None, 0)
         };
         let ix = ix + skip;
+        if la.is_none() && skip == 0 {
+            self.curr_group += 1;
+        }
+        if la.is_some() {
+            self.lookaround_depth += 1;
+        }
         let (ix, child) = self.parse_re(ix, depth)?;
+        if la.is_some() {
+            self.lookaround_depth -= 1;
+        }
         let ix = self.optional_whitespace(ix);
         if ix == self.re.len() {
             return Err(Error::UnclosedOpenParen);
@@ -497,10 +871,301 @@ impl<'a> Parser<'a> {
         Ok((ix + 1, result))
     }
 
+    // ix points after `(?|`, the groups of each alternative are numbered starting from the same
+    // number, and the groups after it continue after the highest number of the alternatives
+    fn parse_branch_reset(&mut self, ix: usize, depth: usize) -> Result<(usize, Expr)> {
+        let flags = self.flags;
+        let start_group = self.curr_group;
+        let mut end_group = start_group;
+        let mut children = Vec::new();
+        let mut ix = ix;
+        loop {
+            self.curr_group = start_group;
+            let (next, child) = self.parse_branch(ix, depth)?;
+            children.push(child);
+            end_group = end_group.max(self.curr_group);
+            ix = self.optional_whitespace(next);
+            if !self.re[ix..].starts_with('|') {
+                break;
+            }
+            ix += 1;
+        }
+        self.curr_group = end_group;
+        self.flags = flags;
+        if ix == self.re.len() {
+            return Err(Error::UnclosedOpenParen);
+        } else if self.re.as_bytes()[ix] != b')' {
+            return Err(Error::ParseError);
+        }
+        let expr = if children.len() == 1 {
+            children.pop().unwrap()
+        } else {
+            Expr::BranchReset(children)
+        };
+        Ok((ix + 1, expr))
+    }
+
+    // ix points after `(?#`, skips to after the closing `)`, which can be escaped
+    fn parse_comment(&self, ix: usize) -> Result<(usize, Expr)> {
+        let bytes = self.re.as_bytes();
+        let mut ix = ix;
+        while ix < self.re.len() {
+            match bytes[ix] {
+                b'\\' => ix += 2,
+                b')' => return Ok((ix + 1, Expr::Empty)),
+                _ => ix += 1,
+            }
+        }
+        Err(Error::UnclosedOpenParen)
+    }
+
+    // ix points after `\Q`, returns the position after `\E` (or the end) and a literal for each
+    // char in between
+    fn parse_quote(&self, ix: usize) -> (usize, Vec<Expr>) {
+        let (next, quoted) = self.quoted_text(ix);
+        let casei = self.flag(FLAG_CASEI);
+        let unicode = self.flag(FLAG_UNICODE);
+        let literals = quoted
+            .chars()
+            .map(|c| Expr::Literal {
+                val: c.to_string(),
+                casei,
+                unicode,
+            })
+            .collect();
+        (next, literals)
+    }
+
+    // ix points after `\Q`, returns the position after `\E` (or the end) and the text in between
+    fn quoted_text(&self, ix: usize) -> (usize, &'a str) {
+        match self.re[ix..].find("\\E") {
+            Some(len) => (ix + len + 2, &self.re[ix..ix + len]),
+            None => (self.re.len(), &self.re[ix..]),
+        }
+    }
+
+    // ix points after `(?~`, e.g. to `abc)`, `|abc|exp)`, `|abc)` or `|)`
+    fn parse_absent(&mut self, ix: usize, depth: usize) -> Result<(usize, Expr)> {
+        let flags = self.flags;
+        let (ix, expr) = if let Some(rest) = self.re[ix..].strip_prefix('|') {
+            if rest.starts_with(')') {
+                (ix + 1, Expr::AbsentRangeClear)
+            } else {
+                // the absent expression ends at the first `|`, the rest is the expression to match
+                let (ix, absent) = self.parse_branch(ix + 1, depth)?;
+                let ix = self.optional_whitespace(ix);
+                if self.re[ix..].starts_with('|') {
+                    let (ix, child) = self.parse_re(ix + 1, depth)?;
+                    let expr = Expr::Absent {
+                        absent: Box::new(absent),
+                        child: Box::new(child),
+                    };
+                    (ix, expr)
+                } else {
+                    (ix, Expr::AbsentStopper(Box::new(absent)))
+                }
+            }
+        } else {
+            let (ix, absent) = self.parse_re(ix, depth)?;
+            // absent repeater, same as `(?~|absent|\O*)`
+            let child = Expr::Repeat {
+                child: Box::new(self.non_unicode(Expr::Any { newline: true })),
+                lo: 0,
+                hi: usize::MAX,
+                greedy: true,
+            };
+            let expr = Expr::Absent {
+                absent: Box::new(absent),
+                child: Box::new(child),
+            };
+            (ix, expr)
+        };
+        let ix = self.optional_whitespace(ix);
+        self.flags = flags;
+        if ix == self.re.len() {
+            return Err(Error::UnclosedOpenParen);
+        } else if self.re.as_bytes()[ix] != b')' {
+            return Err(Error::ParseError);
+        }
+        Ok((ix + 1, expr))
+    }
+
+    // ix points after the opening delimiter of a subroutine call, e.g. after `\g<` or `(?`
+    fn parse_subroutine_call(&mut self, ix: usize, close: u8) -> Result<(usize, Expr)> {
+        let end = match self.re.as_bytes()[ix..].iter().position(|&b| b == close) {
+            Some(len) => ix + len,
+            None => return Err(Error::InvalidSubroutineCall),
+        };
+        let group = self.resolve_subroutine_call(&self.re[ix..end])?;
+        // The called group is compiled differently, like a group referenced by a backref
+        self.backrefs.insert(group);
+        self.max_called_group = self.max_called_group.max(group);
+        Ok((end + 1, Expr::SubroutineCall(group)))
+    }
+
+    // Resolve the target of a subroutine call to a group number. Unlike backrefs, the group can be
+    // defined after the call, e.g. `(?+1)` calls the next group that is opened.
+    fn resolve_subroutine_call(&mut self, s: &str) -> Result<usize> {
+        if s == "R" || s == "0" {
+            return Ok(0);
+        }
+        if let Some(digits) = s.strip_prefix('+') {
+            return match usize::from_str(digits) {
+                Ok(relative) if relative >= 1 && relative < self.re.len() => {
+                    Ok(self.curr_group + relative)
+                }
+                _ => Err(Error::InvalidSubroutineCall),
+            };
+        }
+        if s.starts_with('-') || (!s.is_empty() && s.bytes().all(is_digit)) {
+            return self
+                .resolve_backref(s)
+                .map_err(|_| Error::InvalidSubroutineCall);
+        }
+        let group = self.named_groups.get(s).or_else(|| {
+            self.all_named_groups
+                .as_ref()
+                .and_then(|all_named_groups| all_named_groups.get(s))
+        });
+        match group.map(|groups| groups.as_slice()) {
+            Some(&[group]) => Ok(group),
+            // calling a name that multiple groups have is ambiguous
+            Some(_) => Err(Error::InvalidSubroutineCall),
+            None if self.all_named_groups.is_none() && is_valid_group_name(s) => {
+                // might be defined later, resolved when parsing again
+                self.unresolved_call = true;
+                Ok(0)
+            }
+            None => Err(Error::InvalidSubroutineCall),
+        }
+    }
+
+    // ix points after `(?(`, e.g. to `1)yes|no)` or `?=a)yes|no)`
+    fn parse_conditional(&mut self, ix: usize, depth: usize) -> Result<(usize, Expr)> {
+        let rest = &self.re[ix..];
+        let (ix, condition) = if ["?=", "?!", "?<=", "?<!"]
+            .iter()
+            .any(|prefix| rest.starts_with(prefix))
+        {
+            // look-around condition, parse it as a group starting at the `(`
+            self.parse_group(ix - 1, depth)?
+        } else {
+            // group condition, e.g. `1`, `-1`, `<name>`, `'name'` or `name`
+            let len = rest.find(')').ok_or(Error::UnclosedOpenParen)?;
+            let reference = &rest[..len];
+            let reference = if (reference.starts_with('<') && reference.ends_with('>'))
+                || (reference.len() > 1 && reference.starts_with('\'') && reference.ends_with('\''))
+            {
+                &reference[1..reference.len() - 1]
+            } else {
+                reference
+            };
+            let condition = match self.named_groups.get(reference) {
+                Some(groups) if groups.len() > 1 => {
+                    // any of the groups with the name has to have matched
+                    self.backrefs.extend(groups.iter().copied());
+                    let conditions = groups
+                        .iter()
+                        .map(|&group| Expr::BackrefExistsCondition(group))
+                        .collect();
+                    Expr::Alt(conditions)
+                }
+                _ => {
+                    let group = self.resolve_backref(reference)?;
+                    self.backrefs.insert(group);
+                    Expr::BackrefExistsCondition(group)
+                }
+            };
+            (ix + len + 1, condition)
+        };
+        let (ix, body) = self.parse_re(ix, depth)?;
+        let ix = self.optional_whitespace(ix);
+        if ix == self.re.len() {
+            return Err(Error::UnclosedOpenParen);
+        } else if self.re.as_bytes()[ix] != b')' {
+            return Err(Error::ParseError);
+        }
+        // The first alternative is the true branch, the second one the false branch. More
+        // alternatives are an error, e.g. `(?(1)a|b|c)`.
+        let (true_branch, false_branch) = match body {
+            Expr::Alt(children) if children.len() > 2 => return Err(Error::ParseError),
+            Expr::Alt(mut children) => {
+                let false_branch = children.pop().unwrap();
+                (children.pop().unwrap(), false_branch)
+            }
+            body => (body, Expr::Empty),
+        };
+        Ok((
+            ix + 1,
+            Expr::Conditional {
+                condition: Box::new(condition),
+                true_branch: Box::new(true_branch),
+                false_branch: Box::new(false_branch),
+            },
+        ))
+    }
+
+    // ix points after '(', returns the position after the opening of the named group (e.g. after
+    // `?<name>`) and the name, or `None` if it's not a named group
+    fn parse_group_name(&self, ix: usize) -> Result<Option<(usize, String)>> {
+        let (start, close) = if self.re[ix..].starts_with("?P<") {
+            (ix + 3, b'>')
+        } else if self.re[ix..].starts_with("?<")
+            && !self.re[ix..].starts_with("?<=")
+            && !self.re[ix..].starts_with("?<!")
+        {
+            (ix + 2, b'>')
+        } else if self.re[ix..].starts_with("?'") {
+            (ix + 2, b'\'')
+        } else {
+            return Ok(None);
+        };
+        let bytes = self.re.as_bytes();
+        let end = match bytes[start..].iter().position(|&b| b == close) {
+            Some(len) => start + len,
+            None => return Err(Error::InvalidGroupName),
+        };
+        let name = &self.re[start..end];
+        if !is_valid_group_name(name) {
+            return Err(Error::InvalidGroupName);
+        }
+        Ok(Some((end + 1, String::from(name))))
+    }
+
+    // ix points after the name of the group, e.g. after `(?<name>`
+    fn parse_named_group(
+        &mut self,
+        ix: usize,
+        name: String,
+        depth: usize,
+    ) -> Result<(usize, Expr)> {
+        self.curr_group += 1;
+        let groups = self.named_groups.entry(name.clone()).or_default();
+        // with branch reset, groups in different alternatives can have the same number
+        if !groups.contains(&self.curr_group) {
+            groups.push(self.curr_group);
+        }
+        let (ix, child) = self.parse_re(ix, depth)?;
+        let ix = self.optional_whitespace(ix);
+        if ix == self.re.len() {
+            return Err(Error::UnclosedOpenParen);
+        } else if self.re.as_bytes()[ix] != b')' {
+            return Err(Error::ParseError);
+        };
+        Ok((
+            ix + 1,
+            Expr::NamedGroup {
+                name,
+                child: Box::new(child),
+            },
+        ))
+    }
+
     fn parse_flags(&mut self, ix: usize, depth: usize) -> Result<(usize, Expr)> {
         let start = ix + 1;
         let mut ix = start;
         let mut neg = false;
+        let mut reset = false;
         let oldflags = self.flags;
         loop {
             ix = self.optional_whitespace(ix);
@@ -514,12 +1179,21 @@ impl<'a> Parser<'a> {
                 b'U' => self.update_flag(FLAG_SWAP_GREED, neg),
                 b'x' => self.update_flag(FLAG_IGNORE_SPACE, neg),
                 b'u' => {
-                    if neg {
+                    if neg && self.utf8 {
                         return Err(Error::NonUnicodeUnsupported);
                     }
+                    self.update_flag(FLAG_UNICODE, neg);
+                }
+                b'^' => {
+                    // reset to the defaults, only at the start, e.g. `(?^)` or `(?^i:...)`
+                    if ix != start {
+                        return Err(Error::UnknownFlag);
+                    }
+                    reset = true;
+                    self.flags = FLAG_UNICODE;
                 }
                 b'-' => {
-                    if neg {
+                    if neg || reset {
                         return Err(Error::UnknownFlag); // more precise error?
                     }
                     neg = true;
@@ -598,7 +1272,51 @@ fn parse_decimal(s: &str, ix: usize) -> Option<(usize, usize)> {
 }
 
 fn is_digit(b: u8) -> bool {
-    b'0' <= b && b <= b'9'
+    b.is_ascii_digit()
+}
+
+// Group names have to be valid for the regex crate too, because we pass them through when
+// delegating.
+fn is_valid_group_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    !bytes.is_empty()
+        && !is_digit(bytes[0])
+        && bytes
+            .iter()
+            .all(|&b| b == b'_' || b.is_ascii_alphanumeric())
+}
+
+// Parse up to max_len octal digits starting at ix, e.g. `12` for `\012`
+fn parse_octal(s: &str, ix: usize, max_len: usize) -> (usize, u32) {
+    let bytes = s.as_bytes();
+    let mut end = ix;
+    let mut value = 0;
+    while end < s.len() && end < ix + max_len && is_octal_digit(bytes[end]) {
+        value = value * 8 + u32::from(bytes[end] - b'0');
+        end += 1;
+    }
+    (end, value)
+}
+
+// Parse a number in the given radix up to a closing `}`, e.g. `41}` for `\x{41}`, returning the
+// position after the `}`
+fn parse_braced_number(s: &str, ix: usize, radix: u32) -> Option<(usize, u32)> {
+    let len = s[ix..].find('}')?;
+    // at most 8 digits to avoid overflow
+    if len == 0 || len > 8 {
+        return None;
+    }
+    let digits = &s[ix..ix + len];
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    u32::from_str_radix(digits, radix)
+        .ok()
+        .map(|value| (ix + len + 1, value))
+}
+
+fn is_octal_digit(b: u8) -> bool {
+    (b'0'..=b'7').contains(&b)
 }
 
 fn is_hex_digit(b: u8) -> bool {
@@ -609,15 +1327,15 @@ pub(crate) fn make_literal(s: &str) -> Expr {
     Expr::Literal {
         val: String::from(s),
         casei: false,
+        unicode: true,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parse::make_literal;
+    use crate::parse::{make_literal, Parser, FLAG_CASEI};
     use crate::Expr;
     use crate::LookAround::*;
-    use std::usize;
 
     fn p(s: &str) -> Expr {
         Expr::parse(s).unwrap().0
@@ -689,6 +1407,78 @@ mod tests {
         assert_eq!(p("\\x{000000B7}"), make_literal("\u{B7}"));
     }
 
+    #[test]
+    fn octal_escape() {
+        assert_eq!(p("\\0"), make_literal("\0"));
+        assert_eq!(p("\\012"), make_literal("\n"));
+        assert_eq!(
+            p("\\0123"),
+            Expr::Concat(vec![make_literal("\n"), make_literal("3")])
+        );
+        assert_eq!(p("\\101"), make_literal("A"));
+        assert_eq!(p("\\1010"), p("A0"));
+        assert_eq!(p("\\o{101}"), make_literal("A"));
+        assert_eq!(p("\\o{20254}"), make_literal("\u{20AC}"));
+        // backref if there are enough groups in the regex, octal otherwise
+        assert_eq!(
+            p("(a)\\1"),
+            Expr::Concat(vec![
+                Expr::Group(Box::new(make_literal("a"))),
+                Expr::Backref {
+                    group: 1,
+                    casei: false,
+                    unicode: true,
+                },
+            ])
+        );
+        assert_eq!(
+            p("(a)\\12"),
+            Expr::Concat(vec![
+                Expr::Group(Box::new(make_literal("a"))),
+                make_literal("\n"),
+            ])
+        );
+        // a single digit is always a backref, also to a group that comes later
+        assert_eq!(
+            p("\\1(a)"),
+            Expr::Concat(vec![
+                Expr::Backref {
+                    group: 1,
+                    casei: false,
+                    unicode: true,
+                },
+                Expr::Group(Box::new(make_literal("a"))),
+            ])
+        );
+        let groups = "(a)".repeat(10);
+        assert_eq!(
+            p(&format!("\\10{}", groups)),
+            p(&format!("\\k<10>{}", groups))
+        );
+        assert_eq!(p("[\\1]"), p("[\\x01]"));
+        fail("\\o{}");
+        fail("\\o{8}");
+        fail("\\o101");
+    }
+
+    #[test]
+    fn control_escape() {
+        assert_eq!(p("\\ca"), make_literal("\x01"));
+        assert_eq!(p("\\cA"), make_literal("\x01"));
+        assert_eq!(p("\\C-b"), make_literal("\x02"));
+        assert_eq!(p("\\c\\\\"), make_literal("\x1C"));
+        assert_eq!(p("\\c?"), make_literal("\x7F"));
+        assert_eq!(p("\\M-a"), make_literal("\u{E1}"));
+        assert_eq!(p("\\M-\\C-a"), make_literal("\u{81}"));
+        assert_eq!(p("\\c\\M-a"), make_literal("\u{81}"));
+        fail("\\c");
+        fail("\\C");
+        fail("\\Ca");
+        fail("\\M-");
+        fail("\\cé");
+        fail("\\M-\\M-\\M-a");
+    }
+
     #[test]
     fn hex_escape() {
         assert_eq!(
@@ -743,6 +1533,35 @@ mod tests {
         assert_eq!(p("(a)"), Expr::Group(Box::new(make_literal("a"),)));
     }
 
+    #[test]
+    fn named_group() {
+        let named = |name: &str| Expr::NamedGroup {
+            name: String::from(name),
+            child: Box::new(make_literal("a")),
+        };
+        assert_eq!(p("(?<name>a)"), named("name"));
+        assert_eq!(p("(?P<name>a)"), named("name"));
+        assert_eq!(p("(?'name'a)"), named("name"));
+        assert_eq!(p("(?<n_3>a)"), named("n_3"));
+        assert_eq!(
+            p("(?<=a)(?<!a)"),
+            Expr::Concat(vec![
+                Expr::LookAround(Box::new(make_literal("a")), LookBehind),
+                Expr::LookAround(Box::new(make_literal("a")), LookBehindNeg),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_named_group() {
+        fail("(?<>a)");
+        fail("(?<1>a)");
+        fail("(?<a-b>a)");
+        fail("(?<name");
+        fail("(?'name>a)");
+        fail("(?<name>a");
+    }
+
     #[test]
     fn group_repeat() {
         assert_eq!(
@@ -929,11 +1748,105 @@ mod tests {
             p("(.)\\1"),
             Expr::Concat(vec![
                 Expr::Group(Box::new(Expr::Any { newline: false })),
-                Expr::Backref(1),
+                Expr::Backref {
+                    group: 1,
+                    casei: false,
+                    unicode: true,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn named_backref() {
+        let expected = Expr::Concat(vec![
+            Expr::NamedGroup {
+                name: String::from("n"),
+                child: Box::new(Expr::Any { newline: false }),
+            },
+            Expr::Backref {
+                group: 1,
+                casei: false,
+                unicode: true,
+            },
+        ]);
+        assert_eq!(p("(?<n>.)\\k<n>"), expected);
+        assert_eq!(p("(?<n>.)\\k'n'"), expected);
+        assert_eq!(p("(?<n>.)\\k{n}"), expected);
+        assert_eq!(p("(?<n>.)\\g{n}"), expected);
+        assert_eq!(p("(?<n>.)(?P=n)"), expected);
+        assert_eq!(p("(?<n>.)\\k<1>"), expected);
+        assert_eq!(p("(?<n>.)\\g{1}"), expected);
+        assert_eq!(p("(?<n>.)\\g1"), expected);
+    }
+
+    #[test]
+    fn backref_casei() {
+        assert_eq!(
+            p("(?i)(.)\\1"),
+            Expr::Concat(vec![
+                Expr::Group(Box::new(Expr::Any { newline: false })),
+                Expr::Backref {
+                    group: 1,
+                    casei: true,
+                    unicode: true,
+                },
             ])
         );
     }
 
+    #[test]
+    fn relative_backref() {
+        assert_eq!(
+            p("(a)(.)\\g{-1}\\k<-2>\\g-1"),
+            Expr::Concat(vec![
+                Expr::Group(Box::new(make_literal("a"))),
+                Expr::Group(Box::new(Expr::Any { newline: false })),
+                Expr::Backref {
+                    group: 2,
+                    casei: false,
+                    unicode: true,
+                },
+                Expr::Backref {
+                    group: 1,
+                    casei: false,
+                    unicode: true,
+                },
+                Expr::Backref {
+                    group: 2,
+                    casei: false,
+                    unicode: true,
+                },
+            ])
+        );
+        // counts from the last opened group, even if it's not closed
+        assert_eq!(
+            p("(a(b)\\g{-2})"),
+            Expr::Group(Box::new(Expr::Concat(vec![
+                make_literal("a"),
+                Expr::Group(Box::new(make_literal("b"))),
+                Expr::Backref {
+                    group: 1,
+                    casei: false,
+                    unicode: true,
+                },
+            ])))
+        );
+    }
+
+    #[test]
+    fn invalid_named_backref() {
+        fail("\\k<n>");
+        fail("(?<n>.)\\k<m>");
+        fail("(?<n>.)\\k<n");
+        fail("(?<n>.)\\k");
+        fail("(.)\\k<-2>");
+        fail("(.)\\k<-0>");
+        fail("(.)\\g{-2}");
+        fail("(.)\\g-");
+        fail("(?P=n)");
+    }
+
     #[test]
     fn lookaround() {
         assert_eq!(
@@ -985,6 +1898,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flag_scope() {
+        // a flag set in a group applies until the end of the group, for each flag
+        assert_eq!(p("(a(?i)b)c"), p("(a(?i:b))c"));
+        assert_eq!(p("((?m)^)^"), p("((?m:^))^"));
+        assert_eq!(p("((?s).)."), p("((?s:.))."));
+        assert_eq!(p("((?U)a*)a*"), p("((?U:a*))a*"));
+        assert_eq!(p("((?x)a b) c"), p("((?x:a b)) c"));
+        let bytes = |s| Parser::parse_bytes_with_flags(s, 0).unwrap().0;
+        assert_eq!(bytes("((?-u).)."), bytes("((?-u:.))."));
+        // also in the following alternatives of the group
+        assert_eq!(p("(a(?i)b|c)d"), p("(a(?i:b)|(?i:c))d"));
+        // and for all kinds of groups
+        assert_eq!(p("(?<n>(?i)a)a"), p("(?<n>(?i:a))a"));
+        assert_eq!(p("(?=(?i)a)a"), p("(?=(?i:a))a"));
+        assert_eq!(p("(?>(?i)a)a"), p("(?>(?i:a))a"));
+        assert_eq!(p("(?|(?i)a|b)c"), p("(?|(?i:a)|(?i:b))c"));
+        assert_eq!(p("(?~|(?i)a|b)c"), p("(?~|(?i:a)|(?i:b))c"));
+        assert_eq!(p("(?(?=(?i)a)b|c)d"), p("(?(?=(?i:a))b|c)d"));
+    }
+
+    #[test]
+    fn flag_reset() {
+        assert_eq!(p("(?imsxU)a(?^) b. *$"), p("(?imsxU:a) b. *$"));
+        assert_eq!(p("(?s)(?^i).a"), p("(?i).a"));
+        assert_eq!(p("(?i)(?^:a)a"), p("a(?i:a)"));
+        // flags that are set with the builder are reset too
+        let casei = |s| Parser::parse_with_flags(s, FLAG_CASEI).unwrap().0;
+        assert_eq!(casei("(?^)a"), p("a"));
+        let bytes = |s| Parser::parse_bytes_with_flags(s, 0).unwrap().0;
+        assert_eq!(bytes("(?-u)(?^)."), bytes("."));
+        fail("(?^-i)");
+        fail("(?i^)");
+    }
+
     #[test]
     fn flag_multiline() {
         assert_eq!(p("^"), Expr::StartText);
@@ -993,6 +1941,262 @@ mod tests {
         assert_eq!(p("(?m:$)"), Expr::EndLine);
     }
 
+    #[test]
+    fn continue_from_previous_match_end() {
+        assert_eq!(
+            p(r"\Ga"),
+            Expr::Concat(vec![
+                Expr::ContinueFromPreviousMatchEnd,
+                Expr::Literal {
+                    val: String::from("a"),
+                    casei: false,
+                    unicode: true,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn keep_out() {
+        assert_eq!(
+            p(r"a\K"),
+            Expr::Concat(vec![make_literal("a"), Expr::KeepOut])
+        );
+        fail(r"(?=a\K)");
+        fail(r"(?<!(a\K))");
+    }
+
+    #[test]
+    fn absent() {
+        let any_repeat = Expr::Repeat {
+            child: Box::new(Expr::Any { newline: true }),
+            lo: 0,
+            hi: usize::MAX,
+            greedy: true,
+        };
+        assert_eq!(
+            p(r"(?~ab)"),
+            Expr::Absent {
+                absent: Box::new(p("ab")),
+                child: Box::new(any_repeat),
+            }
+        );
+        assert_eq!(
+            p(r"(?~|ab|c|d)"),
+            Expr::Absent {
+                absent: Box::new(p("ab")),
+                child: Box::new(Expr::Alt(vec![make_literal("c"), make_literal("d")])),
+            }
+        );
+        assert_eq!(p(r"(?~|ab)"), Expr::AbsentStopper(Box::new(p("ab"))));
+        assert_eq!(p(r"(?~|)"), Expr::AbsentRangeClear);
+        fail(r"(?~ab");
+        fail(r"(?~|ab|c");
+    }
+
+    #[test]
+    fn branch_reset() {
+        let group = |child| Expr::Group(Box::new(child));
+        assert_eq!(
+            p(r"(?|(a)|(b)(c))(d)\3"),
+            Expr::Concat(vec![
+                Expr::BranchReset(vec![
+                    group(make_literal("a")),
+                    Expr::Concat(vec![group(make_literal("b")), group(make_literal("c"))]),
+                ]),
+                group(make_literal("d")),
+                Expr::Backref {
+                    group: 3,
+                    casei: false,
+                    unicode: true,
+                },
+            ])
+        );
+        assert_eq!(p(r"(?|a)"), make_literal("a"));
+        fail(r"(?|a|b");
+    }
+
+    #[test]
+    fn duplicate_group_names() {
+        let named = |child| Expr::NamedGroup {
+            name: "x".to_string(),
+            child: Box::new(child),
+        };
+        assert_eq!(
+            p(r"(?<x>a)(?<x>b)\k<x>"),
+            Expr::Concat(vec![
+                named(make_literal("a")),
+                named(make_literal("b")),
+                Expr::MultiBackref {
+                    groups: vec![1, 2],
+                    casei: false,
+                    unicode: true,
+                },
+            ])
+        );
+        // a name that only one group has so far is a normal backref
+        assert_eq!(
+            p(r"(?<x>a)\k<x>(?<x>b)"),
+            Expr::Concat(vec![
+                named(make_literal("a")),
+                Expr::Backref {
+                    group: 1,
+                    casei: false,
+                    unicode: true,
+                },
+                named(make_literal("b")),
+            ])
+        );
+        assert_eq!(
+            p(r"(?<x>a)(?<x>b)(?(<x>)c)"),
+            Expr::Concat(vec![
+                named(make_literal("a")),
+                named(make_literal("b")),
+                Expr::Conditional {
+                    condition: Box::new(Expr::Alt(vec![
+                        Expr::BackrefExistsCondition(1),
+                        Expr::BackrefExistsCondition(2),
+                    ])),
+                    true_branch: Box::new(make_literal("c")),
+                    false_branch: Box::new(Expr::Empty),
+                },
+            ])
+        );
+        // calls are ambiguous
+        fail(r"(?<x>a)(?<x>b)\g<x>");
+    }
+
+    #[test]
+    fn subroutine_call() {
+        let group = |child| Expr::Group(Box::new(child));
+        let named = |child| Expr::NamedGroup {
+            name: "x".to_string(),
+            child: Box::new(child),
+        };
+        assert_eq!(p(r"(?R)"), Expr::SubroutineCall(0));
+        assert_eq!(p(r"(?0)"), Expr::SubroutineCall(0));
+        assert_eq!(p(r"\g<0>"), Expr::SubroutineCall(0));
+        assert_eq!(
+            p(r"(a)\g<1>"),
+            Expr::Concat(vec![group(make_literal("a")), Expr::SubroutineCall(1)])
+        );
+        assert_eq!(
+            p(r"(a)\g'-1'"),
+            Expr::Concat(vec![group(make_literal("a")), Expr::SubroutineCall(1)])
+        );
+        assert_eq!(
+            p(r"(?1)(a)"),
+            Expr::Concat(vec![Expr::SubroutineCall(1), group(make_literal("a"))])
+        );
+        assert_eq!(
+            p(r"(?+1)(a)"),
+            Expr::Concat(vec![Expr::SubroutineCall(1), group(make_literal("a"))])
+        );
+        for re in &[r"\g<x>(?<x>a)", r"(?&x)(?<x>a)", r"(?P>x)(?<x>a)"] {
+            assert_eq!(
+                p(re),
+                Expr::Concat(vec![Expr::SubroutineCall(1), named(make_literal("a"))])
+            );
+        }
+        // backrefs, not calls
+        assert_eq!(
+            p(r"(a)\g1"),
+            Expr::Concat(vec![
+                group(make_literal("a")),
+                Expr::Backref {
+                    group: 1,
+                    casei: false,
+                    unicode: true,
+                }
+            ])
+        );
+        fail(r"(?2)(a)");
+        fail(r"\g<x>");
+        fail(r"\g<x");
+        fail(r"[\g<0>]");
+    }
+
+    #[test]
+    fn builtin_escapes() {
+        assert_eq!(p(r"\N"), Expr::Any { newline: false });
+        assert_eq!(p(r"\O"), Expr::Any { newline: true });
+        assert_eq!(
+            p(r"\Z"),
+            Expr::LookAround(
+                Box::new(Expr::Concat(vec![
+                    Expr::Repeat {
+                        child: Box::new(Expr::Delegate {
+                            inner: String::from("\\n"),
+                            size: 1,
+                            casei: false,
+                        }),
+                        lo: 0,
+                        hi: 1,
+                        greedy: true,
+                    },
+                    Expr::Delegate {
+                        inner: String::from("\\z"),
+                        size: 0,
+                        casei: false,
+                    },
+                ])),
+                LookAhead
+            )
+        );
+        assert!(matches!(p(r"\R"), Expr::AtomicGroup(_)));
+        assert!(matches!(p(r"\X"), Expr::AtomicGroup(_)));
+        // not allowed in classes
+        fail(r"[\N]");
+        fail(r"[\R]");
+        fail(r"[\X]");
+        fail(r"[\Z]");
+    }
+
+    #[test]
+    fn conditional() {
+        assert_eq!(
+            p("(a)(?(1)b|c)"),
+            Expr::Concat(vec![
+                Expr::Group(Box::new(make_literal("a"))),
+                Expr::Conditional {
+                    condition: Box::new(Expr::BackrefExistsCondition(1)),
+                    true_branch: Box::new(make_literal("b")),
+                    false_branch: Box::new(make_literal("c")),
+                },
+            ])
+        );
+        assert_eq!(
+            p("(?(?=a)ab)"),
+            Expr::Conditional {
+                condition: Box::new(Expr::LookAround(Box::new(make_literal("a")), LookAhead)),
+                true_branch: Box::new(Expr::Concat(vec![make_literal("a"), make_literal("b")])),
+                false_branch: Box::new(Expr::Empty),
+            }
+        );
+        assert_eq!(
+            p("(?<n>a)(?(<n>)b|(?:c|d))"),
+            Expr::Concat(vec![
+                Expr::NamedGroup {
+                    name: String::from("n"),
+                    child: Box::new(make_literal("a")),
+                },
+                Expr::Conditional {
+                    condition: Box::new(Expr::BackrefExistsCondition(1)),
+                    true_branch: Box::new(make_literal("b")),
+                    false_branch: Box::new(Expr::Alt(vec![make_literal("c"), make_literal("d")])),
+                },
+            ])
+        );
+        assert_eq!(p("(?<n>a)(?('n'))"), p("(?<n>a)(?(n))"));
+        assert_eq!(p("(a)(?(-1))"), p("(a)(?(1))"));
+        fail("(a)(?(1)b");
+        fail("(a)(?(1)b|c|d)");
+        fail("(?(?=a)b|c|d)");
+        fail("(a)(?(1");
+        fail("(?(x)a)");
+        fail("(?()a)");
+    }
+
     #[test]
     fn flag_swap_greed() {
         assert_eq!(p("a*"), p("(?U:a*?)"));
@@ -1008,6 +2212,49 @@ mod tests {
         assert!(Expr::parse("(?q:a)").is_err());
     }
 
+    #[test]
+    fn non_unicode() {
+        assert!(Expr::parse("(?-u:.)").is_err());
+
+        let p_bytes = |s| Parser::parse_bytes_with_flags(s, 0).unwrap().0;
+        let delegate = |inner: &str| Expr::Delegate {
+            inner: String::from(inner),
+            size: 1,
+            casei: false,
+        };
+        assert_eq!(p_bytes("(?-u:.)"), delegate("(?-u:.)"));
+        assert_eq!(p_bytes("(?s-u:.)"), delegate("(?s-u:.)"));
+        assert_eq!(p_bytes("(?-u:\\xFF)"), delegate("(?-u:\\xFF)"));
+        assert_eq!(p_bytes("(?-u:[^\\xFF])"), delegate("(?-u:[^\\xFF])"));
+        assert_eq!(p_bytes("(?-u:\\w)"), delegate("(?-u:\\w)"));
+        let non_unicode_literal = |val: &str, casei| Expr::Literal {
+            val: String::from(val),
+            casei,
+            unicode: false,
+        };
+        assert_eq!(p_bytes("(?-u:\\x41)"), non_unicode_literal("A", false));
+        assert_eq!(p_bytes("(?i-u:k)"), non_unicode_literal("k", true));
+        assert_eq!(
+            p_bytes("(?i-u:(a)\\1)"),
+            Expr::Concat(vec![
+                Expr::Group(Box::new(non_unicode_literal("a", true))),
+                Expr::Backref {
+                    group: 1,
+                    casei: true,
+                    unicode: false,
+                },
+            ])
+        );
+        // only inside the group
+        assert_eq!(
+            p_bytes("(?-u:a)."),
+            Expr::Concat(vec![
+                non_unicode_literal("a", false),
+                Expr::Any { newline: false }
+            ])
+        );
+    }
+
     #[test]
     fn lifetime() {
         assert_eq!(
@@ -1064,6 +2311,57 @@ mod tests {
         assert_eq!(p("(?x: a (?-x:#) b )"), p("a#b"));
     }
 
+    #[test]
+    fn comment() {
+        assert_eq!(p("a(?#comment)b"), p("ab"));
+        assert_eq!(p(r"a(?#(\)\\)b"), p("ab"));
+        assert_eq!(p("(?#)"), p(""));
+        assert_eq!(p("a(?#x)|b"), p("a|b"));
+        fail("a(?#b");
+        fail(r"a(?#b\)");
+    }
+
+    #[test]
+    fn quote() {
+        let lit = |val: &str, casei| Expr::Literal {
+            val: val.to_string(),
+            casei,
+            unicode: true,
+        };
+        assert_eq!(
+            p(r"\Qa.*\E"),
+            Expr::Concat(vec![lit("a", false), lit(".", false), lit("*", false)])
+        );
+        assert_eq!(p(r"\Qa\E"), lit("a", false));
+        assert_eq!(p(r"\Q\E"), p(""));
+        // until the end without \E
+        assert_eq!(
+            p(r"\Q(a"),
+            Expr::Concat(vec![lit("(", false), lit("a", false)])
+        );
+        // a repeat only applies to the last char
+        assert_eq!(
+            p(r"\Qab\E+"),
+            Expr::Concat(vec![
+                lit("a", false),
+                Expr::Repeat {
+                    child: Box::new(lit("b", false)),
+                    lo: 1,
+                    hi: usize::MAX,
+                    greedy: true,
+                }
+            ])
+        );
+        assert_eq!(
+            p(r"(?i)\Qé \E"),
+            Expr::Concat(vec![lit("é", true), lit(" ", true)])
+        );
+        assert_eq!(p(r"(?x)\Q a \E"), p(r"(?x)\ a\ "));
+        // in a character class
+        assert_eq!(p(r"[\Q]\E]"), p(r"[\]]"));
+        assert_eq!(p(r"[a\Q^-\Eb]"), p(r"[a\^\-b]"));
+    }
+
     #[test]
     fn atomic_group() {
         assert_eq!(p("(?>a)"), Expr::AtomicGroup(Box::new(make_literal("a"))));
@@ -1103,8 +2401,10 @@ mod tests {
     #[test]
     fn invalid_backref() {
         // only syntactic tests; see similar test in analyze module
-        fail(".\\12345678"); // unreasonably large number
+        fail(".\\92345678"); // unreasonably large number (and not octal)
         fail(".\\c"); // not decimal
+        fail(r".\k<0>"); // the whole match
+        fail(r".\g{0}");
     }
 
     // found by cargo fuzz, then minimized
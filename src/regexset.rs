@@ -0,0 +1,254 @@
+// Copyright 2016 The Fancy Regex Authors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Matching multiple regexes against a text at once.
+
+use std::fmt;
+use std::slice;
+
+use crate::{compile_regex, Compiled, Error, Match, Regex, RegexOptions, Result};
+
+/// A set of regexes that can be matched against a text at once, reporting which of the regexes
+/// matched.
+///
+/// All the regexes that don't use fancy features are matched in a single pass using
+/// `regex::RegexSet` from the regex crate. The others are matched one after the other with
+/// backtracking, but only if the text contains a literal that every match of the regex has to
+/// contain (if there is such a literal), e.g. `foo` for `(\w+)foo\1`.
+///
+/// # Example
+///
+/// ```rust
+/// # use fancy_regex::RegexSet;
+///
+/// let set = RegexSet::new(&[r"\d+", r"(\w)\1", r"foo(?!bar)", r"baz(?=qux)"]).unwrap();
+/// let matches = set.matches("foobar 42 aa").unwrap();
+/// let matched: Vec<usize> = matches.iter().collect();
+/// assert_eq!(matched, vec![0, 1]);
+/// ```
+pub struct RegexSet {
+    regexes: Vec<Regex>,
+    /// The regexes that don't use fancy features, matched in one pass
+    easy: regex::RegexSet,
+    /// Index in `regexes` for each regex in `easy`
+    easy_indexes: Vec<usize>,
+    /// The regexes that need backtracking
+    fancy: Vec<FancyMember>,
+}
+
+struct FancyMember {
+    /// Index in `regexes`
+    index: usize,
+    required_literal: Option<String>,
+}
+
+/// The result of [`RegexSet::matches`](struct.RegexSet.html#method.matches), i.e. which regexes
+/// of the set matched.
+#[derive(Clone, Debug)]
+pub struct SetMatches {
+    matched_any: bool,
+    matches: Vec<bool>,
+}
+
+/// An iterator over the indexes of the regexes that matched, see
+/// [`SetMatches::iter`](struct.SetMatches.html#method.iter).
+#[derive(Debug)]
+pub struct SetMatchesIter<'a>(std::iter::Enumerate<slice::Iter<'a, bool>>);
+
+impl RegexSet {
+    /// Parse and compile the regexes of the set with default options.
+    ///
+    /// Returns an [`Error`](enum.Error.html) if any of the patterns could not be parsed.
+    pub fn new<I, S>(patterns: I) -> Result<RegexSet>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut regexes = Vec::new();
+        let mut easy_patterns = Vec::new();
+        let mut easy_indexes = Vec::new();
+        let mut fancy = Vec::new();
+        for (index, pattern) in patterns.into_iter().enumerate() {
+            let options = RegexOptions {
+                pattern: pattern.as_ref().to_string(),
+                ..RegexOptions::default()
+            };
+            let (compiled, named_groups) = compile_regex(&options, true)?;
+            match compiled {
+                Compiled::Wrap { ref re, .. } => {
+                    easy_patterns.push(re.clone());
+                    easy_indexes.push(index);
+                }
+                Compiled::Fancy {
+                    ref required_literal,
+                    ..
+                } => fancy.push(FancyMember {
+                    index,
+                    required_literal: required_literal.clone(),
+                }),
+            }
+            regexes.push(Regex::from_compiled(compiled, named_groups, options)?);
+        }
+        let easy = regex::RegexSet::new(&easy_patterns).map_err(Error::InnerError)?;
+        Ok(RegexSet {
+            regexes,
+            easy,
+            easy_indexes,
+            fancy,
+        })
+    }
+
+    /// Returns the number of regexes in the set.
+    pub fn len(&self) -> usize {
+        self.regexes.len()
+    }
+
+    /// Returns true if the set contains no regexes.
+    pub fn is_empty(&self) -> bool {
+        self.regexes.is_empty()
+    }
+
+    /// Returns the patterns of the regexes in the set, in the order they were given.
+    pub fn patterns(&self) -> Vec<&str> {
+        self.regexes.iter().map(|regex| regex.as_str()).collect()
+    }
+
+    /// Check if any of the regexes in the set matches the text.
+    pub fn is_match(&self, text: &str) -> Result<bool> {
+        if self.easy.is_match(text) {
+            return Ok(true);
+        }
+        for member in &self.fancy {
+            if member.could_match(text) && self.regexes[member.index].is_match(text)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns which of the regexes in the set match the text.
+    ///
+    /// Returns an [`Error`](enum.Error.html) if running one of the regexes fails, e.g. because the
+    /// backtrack limit was exceeded.
+    pub fn matches(&self, text: &str) -> Result<SetMatches> {
+        let mut matches = vec![false; self.regexes.len()];
+        for easy_index in self.easy.matches(text).iter() {
+            matches[self.easy_indexes[easy_index]] = true;
+        }
+        for member in &self.fancy {
+            if member.could_match(text) {
+                matches[member.index] = self.regexes[member.index].is_match(text)?;
+            }
+        }
+        Ok(SetMatches {
+            matched_any: matches.iter().any(|&matched| matched),
+            matches,
+        })
+    }
+
+    /// Returns the first match of each regex in the set, or `None` for regexes that don't match.
+    ///
+    /// This is more expensive than [`matches`](#method.matches), so only use it if you need to
+    /// know where the regexes matched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use fancy_regex::RegexSet;
+    ///
+    /// let set = RegexSet::new(&[r"\d+", r"(\w)\1", r"x"]).unwrap();
+    /// let found = set.find_matches("foo 42").unwrap();
+    /// let found: Vec<_> = found.iter().map(|m| m.map(|m| m.as_str())).collect();
+    /// assert_eq!(found, vec![Some("42"), Some("oo"), None]);
+    /// ```
+    pub fn find_matches<'t>(&self, text: &'t str) -> Result<Vec<Option<Match<'t>>>> {
+        let matches = self.matches(text)?;
+        let mut found = Vec::with_capacity(self.regexes.len());
+        for (regex, &matched) in self.regexes.iter().zip(matches.matches.iter()) {
+            found.push(if matched { regex.find(text)? } else { None });
+        }
+        Ok(found)
+    }
+}
+
+impl FancyMember {
+    fn could_match(&self, text: &str) -> bool {
+        match self.required_literal {
+            Some(ref literal) => text.contains(literal.as_str()),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Debug for RegexSet {
+    /// Shows the original regular expressions.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RegexSet({:?})", self.patterns())
+    }
+}
+
+impl SetMatches {
+    /// Returns true if any regex in the set matched.
+    pub fn matched_any(&self) -> bool {
+        self.matched_any
+    }
+
+    /// Returns true if the regex at the given index matched.
+    ///
+    /// # Panics
+    ///
+    /// If the index is not smaller than the number of regexes in the set.
+    pub fn matched(&self, index: usize) -> bool {
+        self.matches[index]
+    }
+
+    /// Returns the number of regexes in the set (not the number of matches).
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Returns an iterator over the indexes of the regexes that matched, in ascending order.
+    pub fn iter(&self) -> SetMatchesIter<'_> {
+        SetMatchesIter(self.matches.iter().enumerate())
+    }
+}
+
+impl<'a> IntoIterator for &'a SetMatches {
+    type Item = usize;
+    type IntoIter = SetMatchesIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for SetMatchesIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        for (index, &matched) in &mut self.0 {
+            if matched {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright 2016 The Fancy Regex Authors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Replacement of matches, modeled after the regex crate's API.
+
+use std::borrow::Cow;
+
+use crate::Captures;
+
+/// Replacer describes types that can be used to replace matches in a string.
+///
+/// Implementations are provided for replacement templates (`&str` and `String`), where `$1` or
+/// `${1}` are replaced by the text of the corresponding group and `$$` by a literal `$`, and for
+/// closures of the form `FnMut(&Captures) -> T` where `T: AsRef<str>`. Use
+/// [`NoExpand`](struct.NoExpand.html) for replacing with a literal string.
+pub trait Replacer {
+    /// Appends text to `dst` to replace the current match.
+    ///
+    /// The current match is represented by `caps`, which is guaranteed to have a match at
+    /// capture group `0`.
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String);
+
+    /// Return a fixed unchanging replacement string, if possible.
+    ///
+    /// When this returns `Some`, the replacement is done without having to compute the capture
+    /// groups for each match, which is faster.
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// Return a `Replacer` that borrows and wraps this `Replacer`.
+    ///
+    /// This is useful when you want to take a generic `Replacer` (which might not be cloneable)
+    /// and use it without consuming it, so it can be used more than once.
+    fn by_ref(&mut self) -> ReplacerRef<'_, Self> {
+        ReplacerRef(self)
+    }
+}
+
+/// By-reference adaptor for a `Replacer`, see [`Replacer::by_ref`](trait.Replacer.html#method.by_ref).
+#[derive(Debug)]
+pub struct ReplacerRef<'a, R: ?Sized>(&'a mut R);
+
+impl<'a, R: Replacer + ?Sized + 'a> Replacer for ReplacerRef<'a, R> {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        self.0.replace_append(caps, dst)
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        self.0.no_expansion()
+    }
+}
+
+/// A replacer that replaces with the given string literally, without expanding `$1` etc.
+#[derive(Debug, Clone)]
+pub struct NoExpand<'t>(pub &'t str);
+
+impl<'t> Replacer for NoExpand<'t> {
+    fn replace_append(&mut self, _: &Captures<'_>, dst: &mut String) {
+        dst.push_str(self.0);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.0))
+    }
+}
+
+impl Replacer for &str {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        caps.expand(self, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl Replacer for &String {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        caps.expand(self, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl Replacer for String {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        caps.expand(self, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl<F, T> Replacer for F
+where
+    F: FnMut(&Captures<'_>) -> T,
+    T: AsRef<str>,
+{
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        dst.push_str((*self)(caps).as_ref());
+    }
+}
+
+// A template without any `$` can be used as is.
+fn no_expansion<T: AsRef<str>>(template: &T) -> Option<Cow<'_, str>> {
+    let s = template.as_ref();
+    if s.contains('$') {
+        None
+    } else {
+        Some(Cow::Borrowed(s))
+    }
+}
+
+/// A reference to a capture group in a replacement template.
+#[derive(Debug, PartialEq)]
+enum CaptureRef<'a> {
+    Number(usize),
+    Named(&'a str),
+}
+
+/// Expand a replacement template, see [`Captures::expand`](struct.Captures.html#method.expand).
+pub(crate) fn expand(caps: &Captures<'_>, mut template: &str, dst: &mut String) {
+    while !template.is_empty() {
+        match template.find('$') {
+            None => break,
+            Some(i) => {
+                dst.push_str(&template[..i]);
+                template = &template[i..];
+            }
+        }
+        if template.as_bytes().get(1) == Some(&b'$') {
+            dst.push('$');
+            template = &template[2..];
+            continue;
+        }
+        let (end, cap_ref) = match find_cap_ref(template) {
+            Some(found) => found,
+            None => {
+                // Not a valid reference, keep the `$` as a literal
+                dst.push('$');
+                template = &template[1..];
+                continue;
+            }
+        };
+        template = &template[end..];
+        let m = match cap_ref {
+            CaptureRef::Number(i) => caps.get(i),
+            CaptureRef::Named(name) => caps.name(name),
+        };
+        if let Some(m) = m {
+            dst.push_str(m.as_str());
+        }
+    }
+    dst.push_str(template);
+}
+
+/// Parse a capture reference like `$1`, `${1}`, `$name` or `${name}` at the start of `template`
+/// (which starts with `$`). Returns the end index of the reference and the reference itself.
+///
+/// Like in the regex crate, the longest possible name is used, so `$1a` refers to the group named
+/// `1a` and not to group 1 followed by `a`. Use `${1}a` for that.
+fn find_cap_ref(template: &str) -> Option<(usize, CaptureRef<'_>)> {
+    let bytes = template.as_bytes();
+    if bytes.len() <= 1 || bytes[0] != b'$' {
+        return None;
+    }
+    let (start, end, braced) = if bytes[1] == b'{' {
+        let end = bytes[2..].iter().position(|&b| b == b'}')? + 2;
+        (2, end, true)
+    } else {
+        let len = bytes[1..]
+            .iter()
+            .take_while(|&&b| b == b'_' || b.is_ascii_alphanumeric())
+            .count();
+        (1, 1 + len, false)
+    };
+    if start == end {
+        return None;
+    }
+    let name = &template[start..end];
+    let cap_ref = match name.parse::<usize>() {
+        Ok(i) => CaptureRef::Number(i),
+        Err(_) => CaptureRef::Named(name),
+    };
+    Some((if braced { end + 1 } else { end }, cap_ref))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_cap_ref, CaptureRef};
+
+    #[test]
+    fn cap_ref_number() {
+        assert_eq!(find_cap_ref("$1"), Some((2, CaptureRef::Number(1))));
+        assert_eq!(find_cap_ref("$12 "), Some((3, CaptureRef::Number(12))));
+        assert_eq!(find_cap_ref("${1}a"), Some((4, CaptureRef::Number(1))));
+    }
+
+    #[test]
+    fn cap_ref_named() {
+        assert_eq!(find_cap_ref("$foo."), Some((4, CaptureRef::Named("foo"))));
+        assert_eq!(find_cap_ref("$1a"), Some((3, CaptureRef::Named("1a"))));
+        assert_eq!(find_cap_ref("${a b}"), Some((6, CaptureRef::Named("a b"))));
+    }
+
+    #[test]
+    fn cap_ref_invalid() {
+        assert_eq!(find_cap_ref("$"), None);
+        assert_eq!(find_cap_ref("$ "), None);
+        assert_eq!(find_cap_ref("${}"), None);
+        assert_eq!(find_cap_ref("${1"), None);
+    }
+}
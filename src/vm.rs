@@ -69,9 +69,16 @@
 //! 5. We continue with the previously saved thread at PC 4 and IX 0 (backtracking)
 //! 6. Both `Lit("a")` and `Lit("c")` match and we reach `End` -> successful match (index 0 to 2)
 
-use regex::Regex;
+use bit_set::BitSet;
+use regex::bytes::Regex;
+use regex_syntax::hir::{Class, ClassUnicode, ClassUnicodeRange, Hir, HirKind};
+use std::ascii;
+use std::cmp::Ordering;
 use std::collections::BTreeSet;
-use std::usize;
+use std::fmt;
+use std::iter;
+use std::str;
+use std::sync::OnceLock;
 
 use crate::prev_codepoint_ix;
 use crate::Error;
@@ -94,6 +101,21 @@ pub enum Insn {
     AnyNoNL,
     /// Match the literal string at the current index
     Lit(String), // should be cow?
+    /// Match the literal string at the current index, ignoring case (using simple case folding)
+    LitCasei(Box<CaseiLiteral>),
+    /// Match a character (or byte) of the class
+    Class(Box<CharClass>),
+    /// Match characters of the class greedily, between `lo` and `hi` times. Each position after
+    /// at least `lo` characters is pushed for backtracking, so it's like a loop of `Class`
+    /// without having to execute an instruction for each character.
+    ClassRepeat {
+        /// The class
+        class: Box<CharClass>,
+        /// Minimum number of matches
+        lo: usize,
+        /// Maximum number of matches
+        hi: usize,
+    },
     /// Split execution into two threads. The two fields are positions of instructions. Execution
     /// first tries the first thread. If that fails, the second position is tried.
     Split(usize, usize),
@@ -153,14 +175,58 @@ pub enum Insn {
     FailNegativeLookAround,
     /// Set IX back by the specified number of characters
     GoBack(usize),
-    /// Back reference to a group number to check
-    Backref(usize),
+    /// Back reference to a group to check, with the start slot of each candidate group (multiple
+    /// for a name that several groups have). The first candidate that has matched and whose text
+    /// matches is used.
+    Backref(Vec<usize>),
+    /// Back reference to a group to check, ignoring case
+    BackrefCasei(Vec<usize>),
+    /// Back reference to a group to check, ignoring the case of ASCII letters only (when Unicode
+    /// mode is disabled)
+    BackrefAsciiCasei(Vec<usize>),
+    /// Check that the group whose end is saved in the specified slot has matched, for conditionals
+    BackrefExistsCondition(usize),
+    /// Check that the current index is the position where the search started (`\G`)
+    ContinueFromPreviousMatchEnd,
+    /// Call the subroutine starting at `target`, saving the values of its slots and the return
+    /// address on the stack
+    Call {
+        /// The first instruction of the subroutine
+        target: usize,
+        /// The first slot that is used by the subroutine
+        slots_start: usize,
+        /// The slot after the last one that is used by the subroutine
+        slots_end: usize,
+        /// The slot for keeping track of the depth of nested calls
+        depth: usize,
+    },
+    /// Return from a subroutine to the address on the stack, restoring the values of its slots
+    Ret {
+        /// The first slot that is used by the subroutine
+        slots_start: usize,
+        /// The slot after the last one that is used by the subroutine
+        slots_end: usize,
+        /// The slot for keeping track of the depth of nested calls
+        depth: usize,
+    },
+    /// Begin of a group that is the target of subroutine calls, pushing the start on the stack
+    BeginGroup,
+    /// End of a group that is the target of subroutine calls, saving the start from the stack and
+    /// the current index into the specified slot and the next one
+    EndGroup(usize),
+    /// Limit the end of the text to the current index (unless it's already before it) for absent
+    /// operators, saving the previous end into the specified slot
+    LimitEnd(usize),
+    /// Set the end of the text back to the value that was saved in the specified slot
+    RestoreEnd(usize),
+    /// Remove the limit of the end of the text
+    ClearEnd,
     /// Begin of atomic group
     BeginAtomic,
     /// End of atomic group
     EndAtomic,
-    /// Delegate matching to the regex crate for a fixed size
-    DelegateSized(Box<Regex>, usize),
+    /// Delegate matching to the regex crate for a fixed size expression without groups
+    DelegateSized(Box<Regex>),
     /// Delegate matching to the regex crate
     Delegate {
         /// The regex
@@ -181,6 +247,216 @@ pub enum Insn {
         /// The last group number
         end_group: usize,
     },
+    /// Go to the next position from the current index where a match can start according to the
+    /// prefilter, or fail if there is none. This is at the start of the loop that tries each
+    /// position when searching, so that text where the regex can't match is skipped.
+    FindStart(Box<Prefilter>),
+}
+
+/// Information for skipping over text where a match of a regex can't start, see
+/// `Insn::FindStart`.
+pub struct Prefilter {
+    /// Searches for a literal that every match has to contain after its start
+    literal: Option<Regex>,
+    /// Whether a match can start with each byte
+    first_bytes: Option<Box<[bool; 256]>>,
+    /// Where a match can start if the regex is anchored
+    anchor: Option<Anchor>,
+}
+
+/// The positions where a match of a regex that is anchored at the start can start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Anchor {
+    /// Only at the start of the text (`^` or `\A`)
+    StartText,
+    /// At the start of the text and after a newline (`^` in multi-line mode)
+    StartLine,
+}
+
+impl Prefilter {
+    /// Returns a prefilter with a literal that every match has to contain after its start, the
+    /// bytes that a match can start with and the anchor of the regex (all optional), or `None` if
+    /// none of them is known.
+    pub(crate) fn new(
+        literal: Option<&str>,
+        first_bytes: Option<[bool; 256]>,
+        anchor: Option<Anchor>,
+    ) -> Option<Prefilter> {
+        if literal.is_none() && first_bytes.is_none() && anchor.is_none() {
+            return None;
+        }
+        let literal =
+            literal.map(|literal| Regex::new(&regex::escape(literal)).expect("escaped literal"));
+        Some(Prefilter {
+            literal,
+            first_bytes: first_bytes.map(Box::new),
+            anchor,
+        })
+    }
+
+    /// Returns the first position from `ix` where a match can start. `literal_at` is the position
+    /// of the literal that was found by a previous call, so that it doesn't have to be searched
+    /// again.
+    fn find_start(&self, s: &[u8], ix: usize, literal_at: &mut Option<usize>) -> Option<usize> {
+        if self.anchor == Some(Anchor::StartText) && ix > 0 {
+            return None;
+        }
+        if let Some(ref literal) = self.literal {
+            if !literal_at.is_some_and(|at| at >= ix) {
+                *literal_at = Some(literal.find_at(s, ix)?.start());
+            }
+        }
+        let mut ix = ix;
+        loop {
+            let start = match self.first_bytes {
+                Some(ref first_bytes) => {
+                    ix + s[ix..].iter().position(|&b| first_bytes[b as usize])?
+                }
+                None => ix,
+            };
+            match self.anchor {
+                Some(Anchor::StartText) if start > 0 => return None,
+                Some(Anchor::StartLine) if start > 0 && s[start - 1] != b'\n' => {
+                    // continue at the start of the next line
+                    ix = start + s[start..].iter().position(|&b| b == b'\n')? + 1;
+                }
+                _ => return Some(start),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Prefilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let first_bytes = self.first_bytes.as_ref().map(|first_bytes| {
+            let bytes = (0..=255).filter(|&b| first_bytes[b as usize]);
+            bytes
+                .flat_map(ascii::escape_default)
+                .map(char::from)
+                .collect::<String>()
+        });
+        f.debug_struct("Prefilter")
+            .field(
+                "literal",
+                &self.literal.as_ref().map(|literal| literal.as_str()),
+            )
+            .field("first_bytes", &first_bytes)
+            .field("anchor", &self.anchor)
+            .finish()
+    }
+}
+
+/// A character class that is matched directly by the VM, see `Insn::Class`.
+#[derive(Debug)]
+pub enum CharClass {
+    /// Sorted ranges of chars (inclusive). Text that is not valid UTF-8 doesn't match.
+    Unicode(Box<[(char, char)]>),
+    /// Sorted ranges of bytes (inclusive), for classes that can match bytes that are not valid
+    /// UTF-8 (e.g. `(?-u:[^a])`)
+    Bytes(Box<[(u8, u8)]>),
+}
+
+impl CharClass {
+    /// Returns the class of a parsed regex that is a class (e.g. `\w` or `[a-z]`), or `None` if
+    /// it's something else.
+    pub(crate) fn from_hir(hir: &Hir) -> Option<CharClass> {
+        match *hir.kind() {
+            HirKind::Class(Class::Unicode(ref class)) => Some(CharClass::Unicode(
+                class
+                    .iter()
+                    .map(|range| (range.start(), range.end()))
+                    .collect(),
+            )),
+            HirKind::Class(Class::Bytes(ref class)) => Some(CharClass::Bytes(
+                class
+                    .iter()
+                    .map(|range| (range.start(), range.end()))
+                    .collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the length of the char (or byte) at `ix` if it's in the class.
+    #[inline]
+    fn matches(&self, s: &[u8], ix: usize) -> Option<usize> {
+        match *self {
+            CharClass::Unicode(ref ranges) => {
+                let (c, len) = match *s.get(ix)? {
+                    b if b < 0x80 => (char::from(b), 1),
+                    _ => decode_char(s, ix)?,
+                };
+                ranges_contain(ranges, c).then_some(len)
+            }
+            CharClass::Bytes(ref ranges) => ranges_contain(ranges, *s.get(ix)?).then_some(1),
+        }
+    }
+}
+
+/// A literal that is matched ignoring case, see `Insn::LitCasei`. The chars that each char of the
+/// literal is equal to are computed when compiling, so that matching only compares chars.
+pub struct CaseiLiteral {
+    literal: String,
+    /// For each char of the literal, the chars that are equal to it ignoring case (including the
+    /// char itself)
+    chars: Box<[Box<[char]>]>,
+}
+
+impl CaseiLiteral {
+    /// Create the literal, using simple case folding if `unicode` is true, otherwise only
+    /// ignoring the case of ASCII letters.
+    pub(crate) fn new(literal: String, unicode: bool) -> CaseiLiteral {
+        let fold = if unicode {
+            case_fold_simple
+        } else {
+            case_fold_ascii
+        };
+        let chars = literal.chars().map(fold).collect();
+        CaseiLiteral { literal, chars }
+    }
+
+    /// Returns the literal as it was written in the regex.
+    pub fn as_str(&self) -> &str {
+        &self.literal
+    }
+
+    /// Returns the end of the match if the literal matches at `ix`. The end can differ from
+    /// `ix + literal.len()` because equal chars may have different lengths, e.g. `s` and `ſ`.
+    /// Text that is not valid UTF-8 doesn't match.
+    #[inline]
+    fn matches(&self, s: &[u8], mut ix: usize) -> Option<usize> {
+        for equal_chars in self.chars.iter() {
+            let (c, len) = match *s.get(ix)? {
+                b if b < 0x80 => (char::from(b), 1),
+                _ => decode_char(s, ix)?,
+            };
+            if !equal_chars.contains(&c) {
+                return None;
+            }
+            ix += len;
+        }
+        Some(ix)
+    }
+}
+
+impl fmt::Debug for CaseiLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CaseiLiteral").field(&self.literal).finish()
+    }
+}
+
+fn ranges_contain<T: Ord + Copy>(ranges: &[(T, T)], value: T) -> bool {
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < value {
+                Ordering::Less
+            } else if start > value {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
 }
 
 /// Sequence of instructions for the VM to execute.
@@ -189,11 +465,40 @@ pub struct Prog {
     /// Instructions of the program
     pub body: Vec<Insn>,
     n_saves: usize,
+    /// The slot for the limit of the end of the text, if the program uses absent operators
+    text_end: Option<usize>,
+    /// The slot for the start of the match before `\K` moved it, if the program uses `\K`
+    match_start: Option<usize>,
+    /// The instructions where whether matching fails only depends on the index, so that trying
+    /// them again at the same index can be skipped when memoizing. `None` if the program can't be
+    /// memoized (e.g. because it contains backrefs).
+    memo_pcs: Option<BitSet>,
 }
 
 impl Prog {
-    pub(crate) fn new(body: Vec<Insn>, n_saves: usize) -> Prog {
-        Prog { body, n_saves }
+    pub(crate) fn new(
+        body: Vec<Insn>,
+        n_saves: usize,
+        text_end: Option<usize>,
+        match_start: Option<usize>,
+        memo_pcs: Option<BitSet>,
+    ) -> Prog {
+        Prog {
+            body,
+            n_saves,
+            text_end,
+            match_start,
+            memo_pcs,
+        }
+    }
+
+    /// The start of the text consumed by the match with the given saves. This is before the start
+    /// of the match if `\K` moved it, e.g. for `a\Kb`, the match is `b` but `ab` is consumed.
+    pub(crate) fn consumed_start(&self, saves: &[usize]) -> usize {
+        match self.match_start {
+            Some(slot) if saves[slot] != usize::MAX => saves[slot],
+            _ => saves[0],
+        }
     }
 
     #[doc(hidden)]
@@ -234,6 +539,52 @@ struct State {
     options: u32,
 }
 
+/// The states (instruction and index) that have already been tried, see `RegexBuilder::memoize`.
+/// When a state is reached again, it must have failed before (or it's in a loop that doesn't
+/// advance), so it can fail right away. That bounds the number of states that are tried to the
+/// length of the program times the length of the text.
+struct Memo<'a> {
+    /// The instructions whose states are memoized
+    pcs: &'a BitSet,
+    /// The visited states, indexed by `(ix - pos) * prog_len + pc`. It only grows as far as the
+    /// search gets into the text, so that e.g. `find_iter` doesn't allocate for the whole rest
+    /// of the text for each match.
+    visited: BitSet,
+    /// The position where the search started, no state before it is memoized
+    pos: usize,
+    prog_len: usize,
+    /// The number of states that can be memoized within the size limit, the ones further into
+    /// the text are not memoized
+    max_states: usize,
+}
+
+impl<'a> Memo<'a> {
+    /// Returns the memo for running the program on the text from `pos`, or `None` if memoizing
+    /// is disabled or not possible for the program.
+    fn new(prog: &'a Prog, pos: usize, options: &RegexOptions) -> Option<Memo<'a>> {
+        if !options.memoize {
+            return None;
+        }
+        Some(Memo {
+            pcs: prog.memo_pcs.as_ref()?,
+            visited: BitSet::new(),
+            pos,
+            prog_len: prog.body.len(),
+            max_states: options.memoize_size_limit.saturating_mul(8),
+        })
+    }
+
+    /// Mark the state as visited, returning false if it was visited before.
+    #[inline]
+    fn visit(&mut self, pc: usize, ix: usize) -> bool {
+        if ix < self.pos || !self.pcs.contains(pc) {
+            return true;
+        }
+        let state = (ix - self.pos) * self.prog_len + pc;
+        state >= self.max_states || self.visited.insert(state)
+    }
+}
+
 // Each element in the stack conceptually represents the entire state
 // of the machine: the pc (index into prog), the index into the
 // string, and the entire vector of saves. However, copying the save
@@ -386,36 +737,175 @@ impl State {
     }
 }
 
-fn codepoint_len_at(s: &str, ix: usize) -> usize {
-    codepoint_len(s.as_bytes()[ix])
+// The text is not necessarily valid UTF-8 (see `bytes::Regex`), so don't go past the end.
+fn codepoint_len_at(s: &[u8], ix: usize) -> usize {
+    codepoint_len(s[ix]).min(s.len() - ix)
+}
+
+#[inline]
+fn matches_literal(s: &[u8], ix: usize, end: usize, literal: &[u8]) -> bool {
+    end <= s.len() && &s[ix..end] == literal
+}
+
+/// Match the text of the first group in `slots` that has matched and whose text matches at `ix`
+/// (compared using `matches`), returning the end of the match. If none of the groups has matched,
+/// the backref doesn't match either.
+fn match_backref(
+    state: &State,
+    s: &[u8],
+    ix: usize,
+    slots: &[usize],
+    matches: fn(&[u8], usize, &[u8]) -> Option<usize>,
+) -> Option<usize> {
+    slots.iter().find_map(|&slot| {
+        let lo = state.get(slot);
+        let hi = state.get(slot + 1);
+        // a group that is still open (e.g. `(a\k<-1>)`) has no end yet, or a stale one
+        if lo == usize::MAX || hi == usize::MAX || hi < lo {
+            return None;
+        }
+        matches(s, ix, &s[lo..hi])
+    })
+}
+
+/// Match `literal` exactly at `ix`, returning the end of the match.
+fn matches_literal_at(s: &[u8], ix: usize, literal: &[u8]) -> Option<usize> {
+    let ix_end = ix + literal.len();
+    matches_literal(s, ix, ix_end, literal).then_some(ix_end)
 }
 
+/// Match `literal` at `ix` ignoring the case of ASCII letters, returning the end of the match.
+/// All other bytes (including those of non-ASCII chars) must be equal.
+fn matches_literal_ascii_casei(s: &[u8], ix: usize, literal: &[u8]) -> Option<usize> {
+    let ix_end = ix + literal.len();
+    (ix_end <= s.len() && s[ix..ix_end].eq_ignore_ascii_case(literal)).then_some(ix_end)
+}
+
+/// Match `literal` at `ix` using simple case folding, returning the end of the match. The end
+/// can differ from `ix + literal.len()` because equivalent chars may have different lengths,
+/// e.g. `s` and `ſ`. Bytes that are not valid UTF-8 are compared ignoring ASCII case.
+fn matches_literal_casei(s: &[u8], mut ix: usize, literal: &[u8]) -> Option<usize> {
+    let mut lit_ix = 0;
+    while lit_ix < literal.len() {
+        if ix == s.len() {
+            return None;
+        }
+        match (decode_char(literal, lit_ix), decode_char(s, ix)) {
+            (Some((lit_char, lit_len)), Some((c, len))) if chars_eq_casei(c, lit_char) => {
+                lit_ix += lit_len;
+                ix += len;
+            }
+            _ if literal[lit_ix].eq_ignore_ascii_case(&s[ix]) => {
+                lit_ix += 1;
+                ix += 1;
+            }
+            _ => return None,
+        }
+    }
+    Some(ix)
+}
+
+/// Returns the length of the char at `ix`, or `None` at the end of the text or if the text at `ix`
+/// is not valid UTF-8 (only possible with `bytes::Regex`).
 #[inline]
-fn matches_literal(s: &str, ix: usize, end: usize, literal: &str) -> bool {
-    // Compare as bytes because the literal might be a single byte char whereas ix
-    // points to a multibyte char. Comparing with str would result in an error like
-    // "byte index N is not a char boundary".
-    end <= s.len() && &s.as_bytes()[ix..end] == literal.as_bytes()
+fn char_len_at(s: &[u8], ix: usize) -> Option<usize> {
+    match s.get(ix) {
+        Some(&b) if b < 0x80 => Some(1),
+        Some(_) => decode_char(s, ix).map(|(_, len)| len),
+        None => None,
+    }
+}
+
+/// Decode the char at `ix` and return it with its length, or `None` if it's not valid UTF-8.
+fn decode_char(s: &[u8], ix: usize) -> Option<(char, usize)> {
+    let len = codepoint_len_at(s, ix);
+    let c = str::from_utf8(&s[ix..ix + len]).ok()?.chars().next()?;
+    Some((c, len))
+}
+
+fn chars_eq_casei(a: char, b: char) -> bool {
+    if a == b {
+        return true;
+    }
+    if a.is_ascii() && b.is_ascii() {
+        return a.eq_ignore_ascii_case(&b);
+    }
+    let folds = case_folds();
+    match folds.binary_search_by_key(&a, |&(c, _)| c) {
+        Ok(i) => folds[i].1.contains(&b),
+        Err(_) => false,
+    }
+}
+
+/// Returns the chars that are equal to another char using simple case folding, sorted, each with
+/// the chars it's equal to (including itself). Computed once, so that comparing chars when
+/// matching doesn't allocate.
+fn case_folds() -> &'static [(char, Box<[char]>)] {
+    static CASE_FOLDS: OnceLock<Vec<(char, Box<[char]>)>> = OnceLock::new();
+    CASE_FOLDS.get_or_init(|| {
+        let hir = regex_syntax::Parser::new()
+            .parse(r"\p{Changes_When_Casemapped}")
+            .unwrap();
+        let mut class = match *hir.kind() {
+            HirKind::Class(Class::Unicode(ref class)) => class.clone(),
+            _ => unreachable!(),
+        };
+        // also adds the chars that are only equal to others by folding, without a case mapping
+        class.case_fold_simple();
+        class
+            .iter()
+            .flat_map(|range| range.start()..=range.end())
+            .map(|c| (c, case_fold_simple(c)))
+            .filter(|(_, equal)| equal.len() > 1)
+            .collect()
+    })
+}
+
+/// Returns the chars that are equal to `c` using simple case folding, including `c` itself.
+fn case_fold_simple(c: char) -> Box<[char]> {
+    let mut class = ClassUnicode::new(iter::once(ClassUnicodeRange::new(c, c)));
+    class.case_fold_simple();
+    class
+        .iter()
+        .flat_map(|range| range.start()..=range.end())
+        .collect()
+}
+
+/// Returns the chars that are equal to `c` ignoring the case of ASCII letters, including `c`.
+fn case_fold_ascii(c: char) -> Box<[char]> {
+    if c.is_ascii_alphabetic() {
+        Box::new([c.to_ascii_lowercase(), c.to_ascii_uppercase()])
+    } else {
+        Box::new([c])
+    }
 }
 
 /// Run the program with trace printing for debugging.
 pub fn run_trace(prog: &Prog, s: &str, pos: usize) -> Result<Option<Vec<usize>>> {
-    run(prog, s, pos, OPTION_TRACE, &RegexOptions::default())
+    run(
+        prog,
+        s.as_bytes(),
+        pos,
+        OPTION_TRACE,
+        &RegexOptions::default(),
+    )
 }
 
 /// Run the program with default options.
 pub fn run_default(prog: &Prog, s: &str, pos: usize) -> Result<Option<Vec<usize>>> {
-    run(prog, s, pos, 0, &RegexOptions::default())
+    run(prog, s.as_bytes(), pos, 0, &RegexOptions::default())
 }
 
-/// Run the program with options.
+/// Run the program with options. The text is usually UTF-8, but doesn't have to be.
 pub(crate) fn run(
     prog: &Prog,
-    s: &str,
+    text: &[u8],
     pos: usize,
     option_flags: u32,
     options: &RegexOptions,
 ) -> Result<Option<Vec<usize>>> {
+    // The text that can be matched, shortened by absent operators
+    let mut s = text;
     let mut state = State::new(prog.n_saves, MAX_STACK, option_flags);
     if option_flags & OPTION_TRACE != 0 {
         println!("pos\tinstruction");
@@ -423,12 +913,20 @@ pub(crate) fn run(
     let mut backtrack_count = 0;
     let mut pc = 0;
     let mut ix = pos;
+    // The position of the required literal of `Insn::FindStart`, once it has been found
+    let mut literal_at = None;
+    let mut memo = Memo::new(prog, pos, options);
     loop {
         // break from this loop to fail, causes stack to pop
         'fail: loop {
             if option_flags & OPTION_TRACE != 0 {
                 println!("{}\t{} {:?}", ix, pc, prog.body[pc]);
             }
+            if let Some(ref mut memo) = memo {
+                if !memo.visit(pc, ix) {
+                    break 'fail;
+                }
+            }
             match prog.body[pc] {
                 Insn::End => {
                     // save of end position into slot 1 is now done
@@ -440,26 +938,46 @@ pub(crate) fn run(
                     }
                     return Ok(Some(state.saves));
                 }
-                Insn::Any => {
-                    if ix < s.len() {
-                        ix += codepoint_len_at(s, ix);
-                    } else {
+                Insn::Any => match char_len_at(s, ix) {
+                    Some(len) => ix += len,
+                    None => break 'fail,
+                },
+                Insn::AnyNoNL => match char_len_at(s, ix) {
+                    Some(len) if s[ix] != b'\n' => ix += len,
+                    _ => break 'fail,
+                },
+                Insn::Lit(ref val) => {
+                    let ix_end = ix + val.len();
+                    if !matches_literal(s, ix, ix_end, val.as_bytes()) {
                         break 'fail;
                     }
+                    ix = ix_end;
                 }
-                Insn::AnyNoNL => {
-                    if ix < s.len() && s.as_bytes()[ix] != b'\n' {
-                        ix += codepoint_len_at(s, ix);
-                    } else {
-                        break 'fail;
+                Insn::LitCasei(ref literal) => match literal.matches(s, ix) {
+                    Some(ix_end) => ix = ix_end,
+                    None => break 'fail,
+                },
+                Insn::Class(ref class) => match class.matches(s, ix) {
+                    Some(len) => ix += len,
+                    None => break 'fail,
+                },
+                Insn::ClassRepeat { ref class, lo, hi } => {
+                    let mut count = 0;
+                    while count < hi {
+                        match class.matches(s, ix) {
+                            Some(len) => {
+                                if count >= lo {
+                                    state.push(pc + 1, ix)?;
+                                }
+                                ix += len;
+                                count += 1;
+                            }
+                            None => break,
+                        }
                     }
-                }
-                Insn::Lit(ref val) => {
-                    let ix_end = ix + val.len();
-                    if !matches_literal(s, ix, ix_end, val) {
+                    if count < lo {
                         break 'fail;
                     }
-                    ix = ix_end;
                 }
                 Insn::Split(x, y) => {
                     state.push(y, ix)?;
@@ -569,19 +1087,96 @@ pub(crate) fn run(
                     }
                     break 'fail;
                 }
-                Insn::Backref(slot) => {
-                    let lo = state.get(slot);
-                    if lo == usize::MAX {
-                        // Referenced group hasn't matched, so the backref doesn't match either
+                Insn::Backref(ref slots) => {
+                    match match_backref(&state, s, ix, slots, matches_literal_at) {
+                        Some(ix_end) => ix = ix_end,
+                        None => break 'fail,
+                    }
+                }
+                Insn::BackrefCasei(ref slots) => {
+                    match match_backref(&state, s, ix, slots, matches_literal_casei) {
+                        Some(ix_end) => ix = ix_end,
+                        None => break 'fail,
+                    }
+                }
+                Insn::BackrefAsciiCasei(ref slots) => {
+                    match match_backref(&state, s, ix, slots, matches_literal_ascii_casei) {
+                        Some(ix_end) => ix = ix_end,
+                        None => break 'fail,
+                    }
+                }
+                Insn::FindStart(ref prefilter) => {
+                    match prefilter.find_start(s, ix, &mut literal_at) {
+                        Some(start) => ix = start,
+                        // no match is possible anymore, not even at a later position
+                        None => break 'fail,
+                    }
+                }
+                Insn::ContinueFromPreviousMatchEnd => {
+                    if ix != pos {
                         break 'fail;
                     }
-                    let hi = state.get(slot + 1);
-                    let ref_text = &s[lo..hi];
-                    let ix_end = ix + ref_text.len();
-                    if !matches_literal(s, ix, ix_end, ref_text) {
+                }
+                Insn::BackrefExistsCondition(slot) => {
+                    if state.get(slot) == usize::MAX {
                         break 'fail;
                     }
-                    ix = ix_end;
+                }
+                Insn::Call {
+                    target,
+                    slots_start,
+                    slots_end,
+                    depth,
+                } => {
+                    let call_depth = state.get(depth);
+                    if call_depth >= options.recursion_limit {
+                        return Err(Error::RecursionLimitExceeded);
+                    }
+                    for slot in slots_start..slots_end {
+                        let value = state.get(slot);
+                        state.stack_push(value);
+                    }
+                    state.stack_push(pc + 1);
+                    state.save(depth, call_depth + 1);
+                    pc = target;
+                    continue;
+                }
+                Insn::Ret {
+                    slots_start,
+                    slots_end,
+                    depth,
+                } => {
+                    let return_pc = state.stack_pop();
+                    for slot in (slots_start..slots_end).rev() {
+                        let value = state.stack_pop();
+                        state.save(slot, value);
+                    }
+                    let call_depth = state.get(depth);
+                    state.save(depth, call_depth - 1);
+                    pc = return_pc;
+                    continue;
+                }
+                Insn::BeginGroup => state.stack_push(ix),
+                Insn::EndGroup(slot) => {
+                    let start = state.stack_pop();
+                    state.save(slot, start);
+                    state.save(slot + 1, ix);
+                }
+                Insn::LimitEnd(save) => {
+                    let slot = prog.text_end.unwrap();
+                    let end = state.get(slot);
+                    state.save(save, end);
+                    state.save(slot, end.min(ix));
+                    s = &text[..ix.min(s.len())];
+                }
+                Insn::RestoreEnd(save) => {
+                    let end = state.get(save);
+                    state.save(prog.text_end.unwrap(), end);
+                    s = &text[..end.min(text.len())];
+                }
+                Insn::ClearEnd => {
+                    state.save(prog.text_end.unwrap(), usize::MAX);
+                    s = text;
                 }
                 Insn::BeginAtomic => {
                     let count = state.backtrack_count();
@@ -591,15 +1186,12 @@ pub(crate) fn run(
                     let count = state.stack_pop();
                     state.backtrack_cut(count);
                 }
-                Insn::DelegateSized(ref inner, size) => {
-                    if inner.is_match(&s[ix..]) {
-                        // We could analyze for ascii-only, and ix += size in
-                        // that case. Unlikely to be speed-limiting though.
-                        for _ in 0..size {
-                            ix += codepoint_len_at(s, ix);
-                        }
-                    } else {
-                        break 'fail;
+                Insn::DelegateSized(ref inner) => {
+                    // Use the length of the match instead of the size of the expression, because
+                    // e.g. `(?-u:.)` matches a single byte and not a whole char.
+                    match inner.find(&s[ix..]) {
+                        Some(m) => ix += m.end(),
+                        _ => break 'fail,
                     }
                 }
                 Insn::Delegate {
@@ -656,6 +1248,10 @@ pub(crate) fn run(
         let (newpc, newix) = state.pop();
         pc = newpc;
         ix = newix;
+        if let Some(slot) = prog.text_end {
+            // the end of the text might have been limited after the branch was pushed
+            s = &text[..state.get(slot).min(text.len())];
+        }
     }
 }
 
@@ -680,6 +1276,36 @@ mod tests {
         assert!(state.stack.is_empty());
     }
 
+    #[test]
+    fn casei_literal() {
+        let literal = CaseiLiteral::new("sk".to_string(), true);
+        assert_eq!(literal.matches(b"xSK", 1), Some(3));
+        // the matched chars can be longer than the ones of the literal
+        assert_eq!(literal.matches("ſ\u{212A}".as_bytes(), 0), Some(5));
+        assert_eq!(literal.matches(b"s", 0), None);
+        assert_eq!(literal.matches(b"s\xFF", 0), None);
+    }
+
+    #[test]
+    fn casei_literal_ascii() {
+        let literal = CaseiLiteral::new("sk".to_string(), false);
+        assert_eq!(literal.matches(b"xSK", 1), Some(3));
+        assert_eq!(literal.matches("ſ\u{212A}".as_bytes(), 0), None);
+        assert_eq!(literal.matches("s\u{212A}".as_bytes(), 0), None);
+    }
+
+    #[test]
+    fn chars_eq_casei_folds() {
+        assert!(chars_eq_casei('k', '\u{212A}'));
+        assert!(chars_eq_casei('\u{212A}', 'K'));
+        assert!(chars_eq_casei('ſ', 'S'));
+        // both fold to `θ`, but neither is the case mapping of the other
+        assert!(chars_eq_casei('\u{3D1}', '\u{3F4}'));
+        assert!(chars_eq_casei('ǅ', 'ǆ'));
+        assert!(!chars_eq_casei('é', 'e'));
+        assert!(!chars_eq_casei('\u{212A}', 'x'));
+    }
+
     #[test]
     fn state_save_override() {
         let mut state = State::new(1, MAX_STACK, 0);
@@ -835,10 +1461,7 @@ mod tests {
 
             // Remember state of saves for checking later
             expected.push(saves.clone());
-            let mut actual_saves = vec![usize::MAX; slots];
-            for i in 0..slots {
-                actual_saves[i] = state.get(i);
-            }
+            let actual_saves: Vec<usize> = (0..slots).map(|i| state.get(i)).collect();
             actual.push(actual_saves);
         }
 
@@ -0,0 +1,241 @@
+use fancy_regex::bytes::{Regex, RegexBuilder};
+use fancy_regex::Error;
+
+#[test]
+fn match_wrap() {
+    assert!(regex(r"\d+").is_match(b"\xFFab12").unwrap());
+    assert!(!regex(r"\d+").is_match(b"\xFFab").unwrap());
+}
+
+#[test]
+fn match_fancy() {
+    assert!(regex(r"(\w+)\1").is_match(b"\xFFabab").unwrap());
+    assert!(!regex(r"(\w+)\1").is_match(b"\xFFab").unwrap());
+}
+
+#[test]
+fn find_wrap() {
+    assert_eq!(find(r"b+", b"\xFFabbc"), Some((2, 4)));
+    assert_eq!(find(r"\bb", b"ab\xFFb"), Some((3, 4)));
+}
+
+#[test]
+fn find_fancy() {
+    assert_eq!(find(r"(a)\1(?=\xFF)", b"\xFFaa\xC3\xBF"), Some((1, 3)));
+    assert_eq!(find(r"(?<=\xFF)(a)\1", b"\xFFaa\xC3\xBFaa"), Some((5, 7)));
+}
+
+#[test]
+fn find_non_unicode() {
+    assert_eq!(find(r"(?-u)(.)\1", b"a\xFF\xFFb"), Some((1, 3)));
+    assert_eq!(find(r"(?-u:\xFF+)(?=a)", b"\xFF\xFF\xFFa"), Some((0, 3)));
+    assert_eq!(find(r"(?-u:[^a])+(?=a)", b"a\xE9\x00\xFFa"), Some((1, 4)));
+    assert_eq!(find(r"(?-u:[\xE9])(?=a)", b"\xE9a"), Some((0, 1)));
+    // `.` in Unicode mode doesn't match invalid UTF-8
+    assert_eq!(find(r"(.)\1", b"\xFF\xFF"), None);
+    assert_eq!(find(r"(.)\1", b"\xC3\xA9\xC3\xA9"), Some((0, 4)));
+}
+
+#[test]
+fn find_non_unicode_flag_scope() {
+    assert_eq!(find(r"(?-u:.)(.)\1", b"\xFF\xC3\xA9\xC3\xA9"), Some((0, 5)));
+    assert_eq!(find(r"(?-u:.)(.)\1", b"\xFF\xFF\xFF"), None);
+}
+
+#[test]
+fn find_prefilter() {
+    assert_eq!(find(r"(?-u)[\x80\xFF](?=b)", b"a\x80\xFFb"), Some((2, 3)));
+    assert_eq!(find(r"[é](?=b)", b"\xFF\xC3\xA9b"), Some((1, 3)));
+    assert_eq!(find(r"(?i-u)\xE9x(?=y)", b"\xE9X\xE9xy"), Some((2, 4)));
+}
+
+#[test]
+fn find_char_classes() {
+    assert_eq!(find(r"(?-u)([^a]+)\1", b"\xFF\xFEa\xFF\xFF"), Some((3, 5)));
+    assert_eq!(find(r"(\w+)\1", b"a\xFF\xC3\xA9\xC3\xA9"), Some((2, 6)));
+    assert_eq!(find(r"([^a]+)\1", b"\xFF\xFF"), None);
+}
+
+#[test]
+fn find_literal_case_insensitive() {
+    assert_eq!(find(r"(?i)(a)\1b(?=c)", b"\xFFaABc"), Some((1, 4)));
+    assert_eq!(find(r"(?i)(a)\1é(?=c)", b"aa\xC3c"), None);
+}
+
+#[test]
+fn find_iter() {
+    let re = regex(r"(?-u)\xFF(?!\xFF)");
+    let matches: Vec<_> = re
+        .find_iter(b"a\xFF\xFFb\xFF")
+        .map(|m| {
+            let m = m.unwrap();
+            (m.start(), m.end())
+        })
+        .collect();
+    assert_eq!(matches, vec![(2, 3), (4, 5)]);
+}
+
+#[test]
+fn find_iter_empty_matches() {
+    let re = regex(r"(?=\w)");
+    let matches: Vec<_> = re
+        .find_iter(b"a\xFF\xC3\xA9")
+        .map(|m| m.unwrap().start())
+        .collect();
+    assert_eq!(matches, vec![0, 2]);
+}
+
+#[test]
+fn find_iter_keep_out() {
+    let re = regex(r"(?-u)\xFF\K");
+    let matches: Vec<_> = re
+        .find_iter(b"\xFF\xFF\xFF")
+        .map(|m| m.unwrap().start())
+        .collect();
+    assert_eq!(matches, vec![1, 2, 3]);
+}
+
+#[test]
+fn builder() {
+    let re = RegexBuilder::new(r"(a)\1 b")
+        .case_insensitive(true)
+        .ignore_whitespace(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match(b"\xFFaAB").unwrap());
+
+    // without memoization, this backtracks exponentially
+    let re = RegexBuilder::new(r"(?i)(a|b|ab)*(?=c)")
+        .backtrack_limit(1000)
+        .memoize(true)
+        .build()
+        .unwrap();
+    assert!(!re.is_match(&b"ab".repeat(28)).unwrap());
+}
+
+#[test]
+fn iter_backtrack_limit() {
+    let re = RegexBuilder::new(r"(?i)(a|b|ab)*(?=c)")
+        .backtrack_limit(100)
+        .build()
+        .unwrap();
+    let text = "ab".repeat(10);
+    let mut matches = re.find_iter(text.as_bytes());
+    assert_eq!(
+        matches.next().unwrap().err(),
+        Some(Error::BacktrackLimitExceeded)
+    );
+    assert!(matches.next().is_none());
+
+    let mut all = re.captures_iter(text.as_bytes());
+    assert_eq!(
+        all.next().unwrap().err(),
+        Some(Error::BacktrackLimitExceeded)
+    );
+    assert!(all.next().is_none());
+}
+
+#[test]
+fn builtin_escapes_non_unicode() {
+    assert_eq!(find(r"(?-u)\X\X", b"\r\n\xFF"), Some((0, 3)));
+    assert_eq!(find(r"(?-u)\N+", b"\xFF\xFE\n"), Some((0, 2)));
+    assert_eq!(find(r"(?-u)\O+", b"\xFF\n\xFE"), Some((0, 3)));
+    assert_eq!(find(r"(?-u)\R+", b"\x85\r\n"), Some((1, 3)));
+    assert_eq!(find(r"\R+", b"\xC2\x85\r\n"), Some((0, 4)));
+}
+
+#[test]
+fn captures() {
+    let re = regex(r"(?-u)(?<first>\xFF)(b)?(\1)");
+    let caps = re.captures(b"a\xFF\xFF").unwrap().unwrap();
+    assert_eq!(caps.len(), 4);
+    assert_eq!(&caps[0], b"\xFF\xFF");
+    assert_eq!(&caps["first"], b"\xFF");
+    assert_eq!(caps.get(1).unwrap().start(), 1);
+    assert!(caps.get(2).is_none());
+    assert_eq!(caps.get(3).unwrap().as_bytes(), b"\xFF");
+
+    let names: Vec<_> = re.capture_names().collect();
+    assert_eq!(names, vec![None, Some("first"), None, None]);
+}
+
+#[test]
+fn captures_iter() {
+    let re = regex(r"(?-u)(\w)=([^;]*);");
+    let pairs: Vec<_> = re
+        .captures_iter(b"a=\xFF;b=;")
+        .map(|caps| {
+            let caps = caps.unwrap();
+            (caps[1].to_vec(), caps[2].to_vec())
+        })
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            (b"a".to_vec(), b"\xFF".to_vec()),
+            (b"b".to_vec(), b"".to_vec())
+        ]
+    );
+}
+
+#[test]
+fn backref_case_insensitive() {
+    assert_eq!(find(r"(?i)(\w+) \1", b"Foo\xFF fOO"), None);
+    assert_eq!(find(r"(?i)(\w+) \1", b"\xFFFoo fOO"), Some((1, 8)));
+    assert_eq!(find(r"(?i-u)(.+) \1", b"\xFFA \xFFa"), Some((0, 5)));
+}
+
+#[test]
+fn case_insensitive_non_unicode() {
+    // only ASCII letters are folded, so `k` doesn't match the Kelvin sign and `s` doesn't match `ſ`
+    assert_eq!(find(r"(?i-u)(a)\1k", "aa\u{212A}".as_bytes()), None);
+    assert_eq!(find(r"(?i-u)(k)\1", "k\u{212A}".as_bytes()), None);
+    assert_eq!(find(r"(?i-u)(a)\1s", "aa\u{17F}".as_bytes()), None);
+    assert_eq!(find(r"(?i-u)(s)\1", "s\u{17F}".as_bytes()), None);
+    assert_eq!(find(r"(?i-u)k", "\u{212A}".as_bytes()), None);
+    assert_eq!(find(r"(?i-u)(k)\1", b"kK"), Some((0, 2)));
+    assert_eq!(find(r"(?i-u)(é)\1", "éÉé".as_bytes()), None);
+    assert_eq!(
+        find(r"(?i-u)sk(?=x)", "ſ\u{212A}x SKx".as_bytes()),
+        Some((7, 9))
+    );
+
+    // with Unicode mode, simple case folding is used
+    assert_eq!(find(r"(?i)(a)\1k", "aa\u{212A}".as_bytes()), Some((0, 5)));
+    assert_eq!(find(r"(?i)(s)\1", "s\u{17F}".as_bytes()), Some((0, 3)));
+}
+
+#[test]
+fn non_unicode_hex_out_of_range() {
+    let result = Regex::new(r"(?-u)\x{100}");
+    assert!(result.is_err());
+    match result.err() {
+        Some(Error::InvalidCodepointValue) => {}
+        _ => panic!("Expected Error::InvalidCodepointValue"),
+    }
+}
+
+#[test]
+fn non_unicode_unsupported_for_str() {
+    let result = fancy_regex::Regex::new(r"(?-u:.)");
+    assert!(result.is_err());
+    match result.err() {
+        Some(Error::NonUnicodeUnsupported) => {}
+        _ => panic!("Expected Error::NonUnicodeUnsupported"),
+    }
+}
+
+fn regex(re: &str) -> Regex {
+    let parse_result = Regex::new(re);
+    assert!(
+        parse_result.is_ok(),
+        "Expected regex '{}' to be compiled successfully, got {:?}",
+        re,
+        parse_result.err()
+    );
+    parse_result.unwrap()
+}
+
+fn find(re: &str, text: &[u8]) -> Option<(usize, usize)> {
+    regex(re).find(text).unwrap().map(|m| (m.start(), m.end()))
+}
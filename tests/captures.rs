@@ -1,4 +1,4 @@
-use fancy_regex::{Captures, Match, Result};
+use fancy_regex::{Captures, Error, Match, RegexBuilder, Result};
 
 mod common;
 
@@ -30,6 +30,59 @@ fn captures_after_lookbehind() {
     assert_match(captures.get(2), "(foo bar)", 9, 18);
 }
 
+#[test]
+fn captures_keep_out() {
+    let captures = captures(r"(\w+)=\K(\w+)", "key=value");
+    assert_eq!(captures.len(), 3);
+    assert_match(captures.get(0), "value", 4, 9);
+    assert_match(captures.get(1), "key", 0, 3);
+    assert_match(captures.get(2), "value", 4, 9);
+}
+
+#[test]
+fn captures_subroutine_call() {
+    // the capture is from the last time the group matched, like in Oniguruma
+    let caps = captures(r"(?<n>a|b)\g<n>", "ab");
+    assert_match(caps.get(0), "ab", 0, 2);
+    assert_match(caps.get(1), "b", 1, 2);
+
+    // the group that ends last wins when recursing
+    let caps = captures(r"(?<n>a\g<n>?b)", "aabb");
+    assert_match(caps.get(1), "aabb", 0, 4);
+    let caps = captures(r"(A\g'0')|B", "AAAAB");
+    assert_match(caps.get(0), "AAAAB", 0, 5);
+    assert_match(caps.get(1), "AAAAB", 0, 5);
+}
+
+#[test]
+fn captures_branch_reset() {
+    let caps = captures(r"(?|(a)|(b)(c))(d)", "bcd");
+    assert_eq!(caps.len(), 4);
+    assert_match(caps.get(1), "b", 0, 1);
+    assert_match(caps.get(2), "c", 1, 2);
+    assert_match(caps.get(3), "d", 2, 3);
+
+    let caps = captures(r"(?|(a)|(b)(c))(d)", "ad");
+    assert_match(caps.get(1), "a", 0, 1);
+    assert!(caps.get(2).is_none());
+    assert_match(caps.get(3), "d", 1, 2);
+}
+
+#[test]
+fn captures_duplicate_names() {
+    let re = common::regex(r"(?<x>a)|(?<x>b)|(c)");
+    let names: Vec<_> = re.capture_names().collect();
+    assert_eq!(names, vec![None, Some("x"), Some("x"), None]);
+
+    // the last group with the name that matched
+    let caps = captures(r"(?<x>a)|(?<x>b)", "b");
+    assert_match(caps.name("x"), "b", 0, 1);
+    let caps = captures(r"(?<x>a)?(?<x>b)?", "a");
+    assert_match(caps.name("x"), "a", 0, 1);
+    let caps = captures(r"(?<x>a)(?<x>b)", "ab");
+    assert_match(caps.name("x"), "b", 1, 2);
+}
+
 #[test]
 fn captures_from_pos() {
     let text = "11 21 33";
@@ -69,6 +122,130 @@ fn captures_from_pos_looking_left() {
     assert_match(captures.get(1), "x", 1, 2);
 }
 
+#[test]
+fn captures_named_wrap() {
+    let captures = captures(r"(?<first>\w+) (?P<second>\w+) (?'third'\w+)?", "a b c");
+    assert_eq!(captures.len(), 4);
+    assert_match(captures.name("first"), "a", 0, 1);
+    assert_match(captures.name("second"), "b", 2, 3);
+    assert_match(captures.name("third"), "c", 4, 5);
+    assert!(captures.name("fourth").is_none());
+    assert_eq!(&captures["second"], "b");
+    assert_eq!(&captures[3], "c");
+}
+
+#[test]
+fn captures_named_wrap_looking_left() {
+    let regex = common::regex(r"\b(?<word>\w)");
+    let captures = assert_captures(regex.captures_from_pos(".x", 1));
+    assert_match(captures.name("word"), "x", 1, 2);
+}
+
+#[test]
+fn captures_named_fancy() {
+    let captures = captures(r"(?<a>\w)\1(?<b>\w)?(?=(?<c>\.))", "xxy. zz");
+    assert_eq!(captures.len(), 4);
+    assert_match(captures.name("a"), "x", 0, 1);
+    assert_match(captures.name("b"), "y", 2, 3);
+    assert_match(captures.name("c"), ".", 3, 4);
+}
+
+#[test]
+fn captures_named_fancy_unmatched() {
+    let captures = captures(r"(?<a>\w)\1(?<b>\w)?", "zz");
+    assert_match(captures.name("a"), "z", 0, 1);
+    assert!(captures.name("b").is_none());
+}
+
+#[test]
+#[should_panic(expected = "no group named 'missing'")]
+fn captures_index_missing_name() {
+    let captures = captures(r"(?<a>\w)\1", "xx");
+    let _ = &captures["missing"];
+}
+
+#[test]
+fn capture_names() {
+    let regex = common::regex(r"(?<a>.)(.)(?P<c>.)");
+    let names: Vec<_> = regex.capture_names().collect();
+    assert_eq!(names, vec![None, Some("a"), None, Some("c")]);
+    assert_eq!(regex.captures_len(), 4);
+
+    let regex = common::regex(r"(?<a>.)\1((?<c>.))");
+    let names: Vec<_> = regex.capture_names().collect();
+    assert_eq!(names, vec![None, Some("a"), None, Some("c")]);
+    assert_eq!(regex.captures_len(), 4);
+}
+
+#[test]
+fn captures_iter_wrap() {
+    let regex = common::regex(r"(\w)(\d)?");
+    let all: Vec<_> = regex.captures_iter("a1 b c3").map(|c| c.unwrap()).collect();
+    assert_eq!(all.len(), 3);
+    assert_match(all[0].get(0), "a1", 0, 2);
+    assert_match(all[0].get(2), "1", 1, 2);
+    assert_match(all[1].get(1), "b", 3, 4);
+    assert!(all[1].get(2).is_none());
+    assert_match(all[2].get(0), "c3", 5, 7);
+    assert_eq!(all[2].len(), 3);
+}
+
+#[test]
+fn captures_iter_wrap_looking_left() {
+    let regex = common::regex(r"\b(\w)");
+    let all: Vec<_> = regex.captures_iter("ab .cd").map(|c| c.unwrap()).collect();
+    assert_eq!(all.len(), 2);
+    assert_match(all[0].get(1), "a", 0, 1);
+    assert_match(all[1].get(1), "c", 4, 5);
+    assert_eq!(all[1].len(), 2);
+}
+
+#[test]
+fn captures_iter_fancy() {
+    let regex = common::regex(r"(\w)\1(?=(.))");
+    let all: Vec<_> = regex
+        .captures_iter("aab ccd ee")
+        .map(|c| c.unwrap())
+        .collect();
+    assert_eq!(all.len(), 2);
+    assert_match(all[0].get(0), "aa", 0, 2);
+    assert_match(all[0].get(2), "b", 2, 3);
+    assert_match(all[1].get(0), "cc", 4, 6);
+    assert_match(all[1].get(1), "c", 4, 5);
+    assert_match(all[1].get(2), "d", 6, 7);
+}
+
+#[test]
+fn captures_iter_empty_matches() {
+    let regex = common::regex(r"(a*)(?=b|$)");
+    let all: Vec<_> = regex.captures_iter("aab").map(|c| c.unwrap()).collect();
+    assert_eq!(all.len(), 2);
+    assert_match(all[0].get(1), "aa", 0, 2);
+    assert_match(all[1].get(1), "", 3, 3);
+}
+
+#[test]
+fn captures_iter_keep_out() {
+    let regex = common::regex(r"(\w)\K");
+    let all: Vec<_> = regex.captures_iter("abc").map(|c| c.unwrap()).collect();
+    assert_eq!(all.len(), 3);
+    assert_match(all[1].get(0), "", 2, 2);
+    assert_match(all[1].get(1), "b", 1, 2);
+    assert_match(all[2].get(0), "", 3, 3);
+}
+
+#[test]
+fn captures_iter_backtrack_limit() {
+    let regex = RegexBuilder::new(r"(x+x+)+(?=y)")
+        .backtrack_limit(1)
+        .build()
+        .unwrap();
+    let mut all = regex.captures_iter("xxxxxxxxxxy");
+    let result = all.next().unwrap();
+    assert_eq!(result.err(), Some(Error::BacktrackLimitExceeded));
+    assert!(all.next().is_none());
+}
+
 fn captures<'a>(re: &str, text: &'a str) -> Captures<'a> {
     let regex = common::regex(re);
     let result = regex.captures(text);
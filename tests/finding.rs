@@ -1,6 +1,6 @@
 mod common;
 
-use fancy_regex::Regex;
+use fancy_regex::{Error, Regex, RegexBuilder};
 
 #[test]
 fn find_wrap() {
@@ -98,6 +98,278 @@ fn backref_with_multibyte() {
     );
 }
 
+#[test]
+fn named_backref() {
+    assert_eq!(find(r"(?<word>\w+) \k<word>", "ab cd cd"), Some((3, 8)));
+    assert_eq!(find(r"(?<word>\w+) (?P=word)", "ab cd cd"), Some((3, 8)));
+}
+
+#[test]
+fn relative_backref() {
+    assert_eq!(find(r"(a)(b)\g{-1}", "abb"), Some((0, 3)));
+    assert_eq!(find(r"(a)(b)\k<-2>", "aba"), Some((0, 3)));
+    assert_eq!(find(r"(a)(b)\g{-2}", "abb"), None);
+    // a reference to a group that is still open doesn't match
+    assert_eq!(find(r"(a\k<-1>)", "aa"), None);
+    assert_eq!(find(r"(a\k<-1>?)", "aa"), Some((0, 1)));
+}
+
+#[test]
+fn backref_case_insensitive() {
+    assert_eq!(find(r"(?i)(a)\1", "aA"), Some((0, 2)));
+    assert_eq!(find(r"(a)(?i)\1", "aA"), Some((0, 2)));
+    assert_eq!(find(r"(?i:(a))\1", "aA"), None);
+    assert_eq!(find(r"(?i)(\w+) \1", "Straße STRASSE"), None);
+    assert_eq!(find(r"(?i)(\w+) \1", "ΣΑΣ σας"), Some((0, 13)));
+    // simple case folding can change the length of the match
+    assert_eq!(find(r"(?i)(s)\1", "sſ"), Some((0, 3)));
+    assert_eq!(find(r"(?i)(ſ)\1x", "ſsx"), Some((0, 4)));
+    assert_eq!(find(r"(?i)(k)\1", "k\u{212A}"), Some((0, 4)));
+}
+
+#[test]
+fn continue_from_previous_match_end() {
+    assert_eq!(find(r"\Ga", "ba"), None);
+    assert_eq!(find(r"\Gb", "ba"), Some((0, 1)));
+    assert_eq!(find(r"a|\Gz", "za"), Some((0, 1)));
+
+    let re = common::regex(r"\Gab");
+    let m = re.find_from_pos("abab", 2).unwrap().unwrap();
+    assert_eq!((m.start(), m.end()), (2, 4));
+    assert!(re.find_from_pos("abcab", 2).unwrap().is_none());
+
+    let caps = common::regex(r"\G(\d)")
+        .captures_from_pos("a1b2", 3)
+        .unwrap()
+        .unwrap();
+    assert_eq!(caps.get(1).unwrap().as_str(), "2");
+}
+
+#[test]
+fn keep_out() {
+    assert_eq!(find(r"foo\Kbar", "foobar"), Some((3, 6)));
+    assert_eq!(find(r"a\K", "xa"), Some((2, 2)));
+    assert_eq!(find(r"(a\Kb|ac\Kd)", "acd"), Some((2, 3)));
+    // the start is restored when backtracking out of the branch with `\K`
+    assert_eq!(find(r"a\Kb|ac", "ac"), Some((0, 2)));
+    assert_eq!(find(r"(?:\w\K)+(?=\d)", "ab1"), Some((2, 2)));
+}
+
+#[test]
+fn keep_out_in_lookaround() {
+    for re in &[r"(?=a\K)", r"(?<=a\K)b", r"(?!(?:a\K))"] {
+        let result = Regex::new(re);
+        assert_eq!(result.err(), Some(Error::KeepOutInLookAround));
+    }
+}
+
+#[test]
+fn end_text_ignore_trailing_newline() {
+    assert_eq!(find(r"a\Z", "a"), Some((0, 1)));
+    assert_eq!(find(r"a\Z", "a\n"), Some((0, 1)));
+    assert_eq!(find(r"a\Z", "a\n\n"), None);
+    assert_eq!(find(r"a\Z", "ab"), None);
+    assert_eq!(find(r"\Z", "ab\n"), Some((2, 2)));
+}
+
+#[test]
+fn line_break() {
+    assert_eq!(find(r"\R", "a\r\nb"), Some((1, 3)));
+    assert_eq!(find(r"\R", "a\rb"), Some((1, 2)));
+    assert_eq!(find(r"\R", "a\u{2028}b"), Some((1, 4)));
+    assert_eq!(find(r"a\R+b", "a\n\r\n\x0Bb"), Some((0, 6)));
+    // `\r\n` is matched as a unit, like in Oniguruma and PCRE
+    assert_eq!(find(r"\R\n", "\r\n"), None);
+}
+
+#[test]
+fn any_escapes() {
+    assert_eq!(find(r"\N+", "ab\ncd"), Some((0, 2)));
+    assert_eq!(find(r"(?s)\N+", "ab\ncd"), Some((0, 2)));
+    assert_eq!(find(r"\O+", "ab\ncd"), Some((0, 5)));
+    assert_eq!(find(r"(?-s:\O+)", "ab\ncd"), Some((0, 5)));
+}
+
+#[test]
+fn grapheme_cluster() {
+    assert_eq!(find(r"^\X$", "\r\n"), Some((0, 2)));
+    assert_eq!(find(r"^\X$", "g\u{308}"), Some((0, 3)));
+    // Hangul syllable made of L, V and T jamo
+    assert_eq!(find(r"^\X$", "\u{1100}\u{1161}\u{11A8}"), Some((0, 9)));
+    // Devanagari consonant with a spacing mark
+    assert_eq!(find(r"^\X$", "\u{937}\u{93F}"), Some((0, 6)));
+    // flags are pairs of regional indicators
+    assert_eq!(find(r"\X", "\u{1F1E9}\u{1F1EA}\u{1F1EB}"), Some((0, 8)));
+    // emoji ZWJ sequence
+    assert_eq!(find(r"^\X$", "\u{1F469}\u{200D}\u{1F4BB}"), Some((0, 11)));
+    assert_eq!(find(r"h\Xllo", "ha\u{300}llo"), Some((0, 7)));
+    assert_eq!(find(r"\X\X", "a\u{300}"), None);
+}
+
+#[test]
+fn conditional_group_matched() {
+    assert_eq!(find(r"(a)?(?(1)b|c)", "ab"), Some((0, 2)));
+    assert_eq!(find(r"(a)?(?(1)b|c)", "c"), Some((0, 1)));
+    assert_eq!(find(r"(a)?(?(1)b|c)", "ac"), Some((1, 2)));
+    assert_eq!(find(r"(a)(?(1)aa|bb)a", "aaaaa"), Some((0, 4)));
+    assert_eq!(find(r"(?:aa|())(?(<1>)aa|bb)a", "aabba"), Some((0, 5)));
+    assert_eq!(
+        find(r"(?:aa|())(?('1')aa|(?:bb|cc))a", "aacca"),
+        Some((0, 5))
+    );
+    assert_eq!(find(r"(a*)(?(-1))aa", "aaaaa"), Some((0, 5)));
+    assert_eq!(find(r"(?<q>')?\w+(?(q)')", "'ab' cd'"), Some((0, 4)));
+    assert_eq!(find(r"(?<q>')?\w+(?(q)')", "cd'"), Some((0, 2)));
+    assert_eq!(find(r"^(<)?\w+(?(1)>)$", "<ab"), None);
+}
+
+#[test]
+fn conditional_invalid_group() {
+    for re in &[r"(?(1)a)(b)", r"(?(2)a)", r"(?(n)a)"] {
+        assert_eq!(Regex::new(re).err(), Some(Error::InvalidBackref));
+    }
+}
+
+#[test]
+fn conditional_too_many_branches() {
+    for re in &[r"(a)?(?(1)b|c|d)", r"(?(?=a)a|b|c)"] {
+        assert_eq!(Regex::new(re).err(), Some(Error::ParseError));
+    }
+}
+
+#[test]
+fn conditional_no_backtracking_into_false_branch() {
+    // once the condition matched, failing the true branch doesn't try the false branch
+    assert_eq!(find(r"^(a)?(?(1)x|a)", "aa"), Some((0, 1)));
+    assert_eq!(find(r"^(a)(?(1)x|a)", "aa"), None);
+    assert_eq!(find(r"(?(?=a)ab|\w)c", "adc"), Some((1, 3)));
+}
+
+#[test]
+fn conditional_look_around() {
+    assert_eq!(find(r"(?(?=\d)\d+|[a-z]+)", "-12"), Some((1, 3)));
+    assert_eq!(find(r"(?(?=\d)\d+|[a-z]+)", "-ab"), Some((1, 3)));
+    assert_eq!(find(r"(?(?!\d)[a-z]+|\d\d)", "1ab"), Some((1, 3)));
+    assert_eq!(find(r"\w(?(?<=a)b|c)", "acab"), Some((2, 4)));
+    assert_eq!(find(r"\w(?(?<!a)b|c)", "acbb"), Some((0, 2)));
+    assert_eq!(find(r"(?<=(?(?=a)a|b))z", "bz"), Some((1, 2)));
+}
+
+#[test]
+fn conditional_repeated() {
+    assert_eq!(find(r"(?:(a)|b)(?(1)A|B){2}", "bBB"), Some((0, 3)));
+    assert_eq!(find(r"(?:(a)|b(?(1)x|y))+", "byaby"), Some((0, 3)));
+    assert_eq!(find(r"(?:(a)|b(?(1)x|y))+", "abx"), Some((0, 3)));
+}
+
+#[test]
+fn subroutine_call() {
+    assert_eq!(find(r"(a|b)\g<1>", "xab"), Some((1, 3)));
+    assert_eq!(find(r"(?<x>\d+)-\g<x>", "a1-23"), Some((1, 5)));
+    assert_eq!(find(r"(?<x>\d+)-\g'x'", "a1-23"), Some((1, 5)));
+    assert_eq!(find(r"(?<x>\d+)-(?&x)", "a1-23"), Some((1, 5)));
+    assert_eq!(find(r"(?P<x>\d+)-(?P>x)", "a1-23"), Some((1, 5)));
+    assert_eq!(find(r"(\d+)-(?1)", "a1-23"), Some((1, 5)));
+    assert_eq!(find(r"(\d+)-(?-1)", "a1-23"), Some((1, 5)));
+    // calls to groups that are defined later
+    assert_eq!(find(r"\g<x>-(?<x>\d+)", "a1-23"), Some((1, 5)));
+    assert_eq!(find(r"(?+1)-(\d+)", "a1-23"), Some((1, 5)));
+    assert_eq!(find(r"\g<+1>-(\d+)", "a1-23"), Some((1, 5)));
+}
+
+#[test]
+fn subroutine_call_recursion() {
+    // balanced parentheses
+    let re = r"\((?:[^()]|(?R))*\)";
+    assert_eq!(find(re, "a(b(c)(d(e)))"), Some((1, 13)));
+    assert_eq!(find(re, "(()"), Some((1, 3)));
+    assert_eq!(find(r"(?<n>a|\(\g<n>\))", "x(((a)))"), Some((1, 8)));
+    assert_eq!(find(r"^(?<n>a\g<n>?b)$", "aaabbb"), Some((0, 6)));
+    assert_eq!(find(r"^(?<n>a\g<n>?b)$", "aaabb"), None);
+    // repeat counts are kept when recursing
+    assert_eq!(find(r"(?<n>a{2}(?:\g<n>)?b)", "aaaabb"), Some((0, 6)));
+    // mutual recursion
+    let re = r"\A(?:\g<pon>|\g<pan>|\z(?<pan>a|c\g<pon>c)(?<pon>b|d\g<pan>d))$";
+    assert_eq!(find(re, "cdcbcdc"), Some((0, 7)));
+}
+
+#[test]
+fn subroutine_call_invalid() {
+    for re in &[r"\g<2>(a)", r"\g<x>", r"(?1)", r"(a)(?-2)", r"\g<+1>"] {
+        assert_eq!(Regex::new(re).err(), Some(Error::InvalidSubroutineCall));
+    }
+}
+
+#[test]
+fn subroutine_call_infinite_left_recursion() {
+    for re in &[
+        r"(?<n>a|\g<n>b)",
+        r"(?R)",
+        r"(a*(?1))",
+        r"(a|(?2))(b|(?1))",
+        r"(a)|(?:x?(?R))",
+    ] {
+        assert_eq!(Regex::new(re).err(), Some(Error::InfiniteLeftRecursion));
+    }
+    // matching something before recursing is fine
+    assert!(Regex::new(r"(?<n>a|b\g<n>)").is_ok());
+    assert!(Regex::new(r"(a|(?2))(b(?1))").is_ok());
+}
+
+#[test]
+fn subroutine_call_recursion_limit() {
+    let regex = RegexBuilder::new(r"(?<n>a\g<n>?)")
+        .recursion_limit(10)
+        .build()
+        .unwrap();
+    assert_eq!(regex.find("aaaaa").unwrap().map(|m| m.end()), Some(5));
+    let text = "a".repeat(20);
+    let result = regex.find(&text);
+    assert_eq!(result.err(), Some(Error::RecursionLimitExceeded));
+}
+
+#[test]
+fn absent_repeater() {
+    assert_eq!(find(r"/\*(?~\*/)\*/", "/* a */ */"), Some((0, 7)));
+    assert_eq!(find(r"(?~abc)", "xxabcyy"), Some((0, 2)));
+    assert_eq!(find(r"(?~abc)", "abc"), Some((0, 0)));
+    assert_eq!(find(r"(?~ab|c)", "xxcab"), Some((0, 2)));
+    // backtracks to shorter matches
+    assert_eq!(find(r"(?~abc)xyz", "xyz0123abc"), Some((0, 3)));
+    // matches across lines
+    assert_eq!(find(r"(?~ab)", "ccc\ndab"), Some((0, 5)));
+    // the absent expression is searched from the current position
+    assert_eq!(find(r"(?~XYZ|ABC)a", "ABCa"), Some((1, 4)));
+}
+
+#[test]
+fn absent_expression() {
+    assert_eq!(find(r"(?~|78|\d*)", "123456789"), Some((0, 6)));
+    assert_eq!(find(r"(?~|ab|\O{1,10})", "ab"), Some((1, 2)));
+    assert_eq!(find(r"(?~|ab|\O{5,10})|abc", "abc"), Some((0, 3)));
+    assert_eq!(find(r"(a)(?~|bb|(?:a\1)*)", "aaaaaa"), Some((0, 5)));
+    // the limit only applies to the expression
+    assert_eq!(find(r"(?~|b|a+)b", "aab"), Some((0, 3)));
+}
+
+#[test]
+fn absent_stopper() {
+    assert_eq!(find(r"(?~|abc)a*", "aaaaaabc"), Some((0, 5)));
+    assert_eq!(find(r"(?~|abc)a*z|aaaaaabc", "aaaaaabc"), Some((0, 8)));
+    // range clear
+    assert_eq!(find(r"(?~|a).*(?~|)a", "bbba"), Some((0, 4)));
+    assert_eq!(find(r"(?~|a).*a", "bbba"), None);
+}
+
+#[test]
+fn find_iter_continue_from_previous_match_end() {
+    // only the tokens at the start, stops at the first gap
+    assert_eq!(
+        find_iter(r"\G\w+,", "ab,c,d, e,"),
+        vec![(0, 3), (3, 5), (5, 7)]
+    );
+}
+
 #[test]
 fn repeat_non_greedy() {
     // (?=a) to make it fancy and use VM
@@ -125,6 +397,149 @@ fn delegates_match_unicode_scalar_value() {
     assert_eq!(find(r".(?=\ba+)", "\u{1F60A}a"), Some((0, 4)));
 }
 
+#[test]
+fn branch_reset() {
+    assert_eq!(find(r"(?|(a)|(b))\1", "abb"), Some((1, 3)));
+    assert_eq!(find(r"(?|(a)|(b)(c))(d)\3", "bcdd"), Some((0, 4)));
+    assert_eq!(find(r"(?|(a)|(b)(c))(d)\3", "add"), Some((0, 3)));
+    // without groups, it's like a non-capturing group
+    assert_eq!(find(r"(?|a|b)+", "xbab"), Some((1, 4)));
+}
+
+#[test]
+fn duplicate_group_names() {
+    let re = r"(?:(?<x>abc)|(?<x>efg))\k<x>";
+    assert_eq!(find(re, "abcabc"), Some((0, 6)));
+    assert_eq!(find(re, "efgefg"), Some((0, 6)));
+    assert_eq!(find(re, "abcefg"), None);
+    // the last group that matched and whose text matches is used
+    assert_eq!(find(r"(?<x>a)(?<x>b)\k<x>", "abb"), Some((0, 3)));
+    assert_eq!(find(r"(?<x>a)(?<x>b)\k<x>", "aba"), Some((0, 3)));
+    assert_eq!(find(r"(?<x>a)(?<x>b)?\k<x>", "aa"), Some((0, 2)));
+    assert_eq!(find(r"(?i)(?<x>a)(?<x>b)\k<x>", "abA"), Some((0, 3)));
+    assert_eq!(find(r"(?:(?<x>a)|(?<x>b))(?(<x>)c|d)", "bc"), Some((0, 2)));
+}
+
+#[test]
+fn prefilter() {
+    // Matching these at every position would exceed the backtrack limit, but the text doesn't
+    // contain the required literal `c` or the first char `x`.
+    let text = "ab".repeat(1000);
+    assert_eq!(find(r"(a|b|ab)*(?=c)", &text), None);
+    assert_eq!(find(r"x(a|b|ab)*(?=d|e)", &text), None);
+    assert_eq!(find(r"(a|b|ab)*(?=c)", &(text + "c")), Some((0, 2000)));
+
+    // skipping to the positions where a match can start
+    assert_eq!(find(r"(?i)k(?=!)", "k \u{212A}!"), Some((2, 5)));
+    assert_eq!(find(r"[é-ë]\w(?=!)", "éaéb!"), Some((3, 6)));
+    assert_eq!(find_iter(r"a(?=b)", "aabab"), vec![(1, 2), (3, 4)]);
+    assert_eq!(find_iter(r"\Gab(?!c)", "ababx"), vec![(0, 2), (2, 4)]);
+
+    // a required literal in a look-behind can be before the position where the search starts
+    let regex = common::regex(r"(?<=foo)bar(?!x)");
+    let m = regex.find_from_pos("foobar", 3).unwrap().unwrap();
+    assert_eq!((m.start(), m.end()), (3, 6));
+}
+
+#[test]
+fn anchored_start() {
+    // Trying every position would exceed the backtrack limit, but a match can only start at 0
+    let text = "a".repeat(2000);
+    assert_eq!(find(r"^(\w+)\s\1$", &text), None);
+    assert_eq!(find(r"\A(\w+)\s\1$|^(?=b)", &text), None);
+    assert_eq!(find(r"^(\w+)\s\1$", "ab ab"), Some((0, 5)));
+
+    // multi-line mode: only at the start of lines
+    let text = "a".repeat(2000) + "\nbb";
+    assert_eq!(find(r"(?m)^(\w)\1$", &text), Some((2001, 2003)));
+    assert_eq!(
+        find_iter(r"(?m)^(\w)\1", "aa\nbc\ncc"),
+        vec![(0, 2), (6, 8)]
+    );
+
+    let regex = common::regex(r"^(a)\1");
+    assert!(regex.find_from_pos("aaaa", 2).unwrap().is_none());
+    let regex = common::regex(r"(?m)^(a)\1");
+    let m = regex.find_from_pos("aa\naa", 1).unwrap().unwrap();
+    assert_eq!((m.start(), m.end()), (3, 5));
+}
+
+#[test]
+fn char_classes() {
+    assert_eq!(find(r"(\w+)\s\1", "xé éy"), Some((1, 6)));
+    assert_eq!(find(r"(\w+)\d\1", "ab1ab"), Some((0, 5)));
+    assert_eq!(find(r"(\d{2,3})\1", "1231231"), Some((0, 6)));
+    assert_eq!(find(r"(\d{2,3})\1", "11212"), Some((1, 5)));
+    assert_eq!(find(r"(\w+?)\1", "baaaa"), Some((1, 3)));
+    assert_eq!(find(r"(?i)([^\sé]+)\1", "é aA ÉÉ"), Some((3, 5)));
+    assert_eq!(find(r"(?i)([k]+)\1", "K\u{212A}"), Some((0, 4)));
+}
+
+#[test]
+fn literal_case_insensitive() {
+    assert_eq!(find(r"(?i)(a)\1bcd(?=e)", "aAbCdE"), Some((0, 5)));
+    assert_eq!(find(r"(?i)(x)\1k(?=!)", "xX\u{212A}!"), Some((0, 5)));
+    assert_eq!(find(r"(?i)(x)\1ß(?=!)", "xxẞ!"), Some((0, 5)));
+    assert_eq!(find(r"(?i)(x)\1ß(?=!)", "xxSS!"), None);
+    assert_eq!(find(r"(?<=(?i)k)x(?!y)", "\u{212A}x"), Some((3, 4)));
+}
+
+#[test]
+fn find_iter_wrap() {
+    assert_eq!(find_iter(r"\d+", "a1 22 333"), vec![(1, 2), (3, 5), (6, 9)]);
+    assert_eq!(find_iter(r"\d+", "abc"), vec![]);
+}
+
+#[test]
+fn find_iter_fancy() {
+    assert_eq!(
+        find_iter(r"(\w)\1", "aabbc dd"),
+        vec![(0, 2), (2, 4), (6, 8)]
+    );
+}
+
+#[test]
+fn find_iter_empty_matches() {
+    assert_eq!(find_iter(r"a*", "baaab"), vec![(0, 0), (1, 4), (5, 5)]);
+    // the empty match at 2 directly follows the previous match, so it's skipped
+    assert_eq!(find_iter(r"a*(?=b|$)", "aab"), vec![(0, 2), (3, 3)]);
+    assert_eq!(
+        find_iter(r"(?=x)|", "\u{1F60A}x"),
+        vec![(0, 0), (4, 4), (5, 5)]
+    );
+}
+
+#[test]
+fn find_iter_keep_out() {
+    // the matches are empty, but the text before them is consumed, so none is skipped
+    assert_eq!(find_iter(r"a\K", "aaa"), vec![(1, 1), (2, 2), (3, 3)]);
+    assert_eq!(find_iter(r"a\Kb?", "aaba"), vec![(1, 1), (2, 3), (4, 4)]);
+}
+
+#[test]
+fn find_iter_looking_left() {
+    // `\b` and look-behind need to see the text before the previous match
+    assert_eq!(find_iter(r"\b\w", "ab cd"), vec![(0, 1), (3, 4)]);
+    assert_eq!(find_iter(r"(?<=a)\w", "aab"), vec![(1, 2), (2, 3)]);
+    assert_eq!(find_iter(r"(?<!a)b", "bab"), vec![(0, 1)]);
+    assert_eq!(
+        find_iter(r"(?m:^)(\d)(?=\d)", "12\n34"),
+        vec![(0, 1), (3, 4)]
+    );
+}
+
+#[test]
+fn find_iter_backtrack_limit() {
+    let regex = RegexBuilder::new(r"(x+x+)+(?=y)")
+        .backtrack_limit(1)
+        .build()
+        .unwrap();
+    let mut matches = regex.find_iter("xxxxxxxxxxy");
+    let result = matches.next().unwrap();
+    assert_eq!(result.err(), Some(Error::BacktrackLimitExceeded));
+    assert!(matches.next().is_none());
+}
+
 fn find(re: &str, text: &str) -> Option<(usize, usize)> {
     let regex = common::regex(re);
     let result = regex.find(text);
@@ -135,3 +550,14 @@ fn find(re: &str, text: &str) -> Option<(usize, usize)> {
     );
     result.unwrap().map(|m| (m.start(), m.end()))
 }
+
+fn find_iter(re: &str, text: &str) -> Vec<(usize, usize)> {
+    let regex = common::regex(re);
+    regex
+        .find_iter(text)
+        .map(|result| {
+            let m = result.expect("Expected find_iter to succeed");
+            (m.start(), m.end())
+        })
+        .collect()
+}
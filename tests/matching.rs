@@ -1,4 +1,4 @@
-use fancy_regex::{Error, RegexBuilder};
+use fancy_regex::{Error, Regex, RegexBuilder};
 
 mod common;
 
@@ -11,6 +11,51 @@ fn control_character_escapes() {
     assert_match(r"\r", "\x0D");
     assert_match(r"\t", "\x09");
     assert_match(r"\v", "\x0B");
+    assert_match(r"\cj", "\x0A");
+    assert_match(r"\C-[", "\x1B");
+    assert_match(r"\0", "\x00");
+    assert_match(r"\033", "\x1B");
+    assert_match(r"\o{33}", "\x1B");
+}
+
+#[test]
+fn octal_escape_or_backref() {
+    assert_match(r"(a)\1", "aa");
+    assert_no_match(r"(a)\1", "a\x01");
+    assert_match(r"(a)\12", "a\n");
+    assert_match(r"(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\10", "abcdefghijj");
+    assert_match(r"(a)(b)(c)(d)(e)(f)(g)(h)(i)\10", "abcdefghi\x08");
+    // a single digit is a backref even if the group comes later, which is invalid
+    assert!(Regex::new(r"\1(.)").is_err());
+}
+
+#[test]
+fn comments_and_quoting() {
+    assert_match(r"a(?#....\\JJJJ)b", "ab");
+    assert_match(r"^a(?#comment)+$", "aa");
+    assert_match(r"^\Q1+1=(2)\E$", "1+1=(2)");
+    assert_no_match(r"^\Q1+1\E$", "11");
+    assert_match(r"^(?i)\QAb\E$", "aB");
+    // the quoted `(` doesn't open a group
+    assert_match(r"^\Q(\E(a)\1$", "(aa");
+    assert_match(r"^[\Q]\E]$", "]");
+    assert_match(r"^[\Q^a-c\E]+$", "a-^");
+    assert_no_match(r"^[\Q^a-c\E]+$", "b");
+}
+
+#[test]
+fn inline_flag_scope() {
+    assert_match(r"^(a(?i)b)c$", "aBc");
+    assert_no_match(r"^(a(?i)b)c$", "aBC");
+    assert_no_match(r"^(a(?i)b)c$", "ABc");
+    assert_match(r"^(a(?i)b|c)d$", "Cd");
+    assert_no_match(r"^(a(?i)b|c)d$", "CD");
+    assert_match(r"^(?=(?i)a)\w(?<=a)$", "a");
+    assert_no_match(r"^(?=(?i)a)\w(?<=a)$", "A");
+    assert_match(r"^((?s).)\1.$", "\n\na");
+    assert_no_match(r"^((?s).)\1.$", "\n\n\n");
+    assert_match(r"(?i)a(?^)b", "Ab");
+    assert_no_match(r"(?i)a(?^)b", "AB");
 }
 
 #[test]
@@ -31,6 +76,11 @@ fn character_class_escapes() {
     // Control characters
     assert_match(r"[\e]", "\x1B");
     assert_match(r"[\n]", "\x0A");
+    assert_match(r"[\ca]", "\x01");
+    assert_match(r"[\M-\C-a]", "\u{81}");
+    assert_match(r"[\1]", "\x01");
+    assert_match(r"[\0-\037]", "\x1F");
+    assert_match(r"[\o{101}-\o{132}]+$", "AZ");
 
     // `]` can be unescaped if it's right after `[`
     assert_match(r"[]]", "]");
@@ -105,19 +155,105 @@ fn backtrack_limit() {
     }
 }
 
+#[test]
+fn memoize() {
+    let build = |re: &str, size_limit: usize| {
+        RegexBuilder::new(re)
+            .backtrack_limit(100_000)
+            .memoize(true)
+            .memoize_size_limit(size_limit)
+            .build()
+            .unwrap()
+    };
+    let re = build("(?i)(a|b|ab)*(?=c)", 1 << 20);
+    let s = "ab".repeat(1000);
+    assert!(!re.is_match(&s).unwrap());
+    let text = s.clone() + "c";
+    let caps = re.captures(&text).unwrap().unwrap();
+    assert_eq!(caps.get(0).unwrap().end(), 2000);
+    assert_eq!(caps.get(1).unwrap().as_str(), "b");
+
+    // not enough memory, so only the states near the start are memoized and it still
+    // backtracks exponentially
+    let re = build("(?i)(a|b|ab)*(?=c)", 100);
+    match re.is_match(&s) {
+        Err(Error::BacktrackLimitExceeded) => {}
+        result => panic!("Expected Error::BacktrackLimitExceeded, got {:?}", result),
+    }
+
+    // same results as without memoization
+    let cases = [
+        (r"(a|ab)(c|bcd)(?=d|$)", "abcd abcdd"),
+        (r"(?:a(?=b)|ab)+(?<!a)c", "aabababac ababc"),
+        (r"(\w+?)(?>b|bc)c(?!x)", "abcc abcx abbc"),
+        (r"(?:(a)|b)+(?(1)c|d)", "bbd abc bad"),
+        (r"(\w)\1(?=x)|y{2,3}(?!y)", "aax yyyy yy"),
+    ];
+    for &(re, text) in &cases {
+        let positions = |regex: &fancy_regex::Regex| {
+            let caps = regex.captures(text).unwrap()?;
+            Some(
+                caps.iter()
+                    .map(|m| m.map(|m| (m.start(), m.end())))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let expected = positions(&common::regex(re));
+        assert_eq!(positions(&build(re, 1 << 20)), expected, "{}", re);
+    }
+
+    // each search only memoizes the text it gets to
+    let re = build(r"(?i)(a|b|ab)*(?=c)", 1 << 10);
+    let text = "abc".repeat(10_000);
+    let matches: Vec<_> = re.find_iter(&text).map(|m| m.unwrap().end()).collect();
+    assert_eq!(matches.len(), 10_000);
+    assert_eq!(matches[..3], [2, 5, 8]);
+}
+
+#[test]
+fn builder_flags() {
+    let re = RegexBuilder::new(r"(a)\1(?=b)")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("aAB").unwrap());
+    assert_eq!(re.as_str(), r"(a)\1(?=b)");
+
+    let re = RegexBuilder::new("^b$").multi_line(true).build().unwrap();
+    assert!(re.is_match("a\nb\nc").unwrap());
+
+    let re = RegexBuilder::new("a.c")
+        .dot_matches_new_line(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("a\nc").unwrap());
+
+    let re = RegexBuilder::new("a b # comment\n c")
+        .ignore_whitespace(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("abc").unwrap());
+
+    let re = RegexBuilder::new("a+").swap_greed(true).build().unwrap();
+    assert_eq!(re.find("aaa").unwrap().unwrap().as_str(), "a");
+
+    // inline flags can still turn them off again
+    let re = RegexBuilder::new("(?-i)a")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(!re.is_match("A").unwrap());
+}
+
 fn assert_match(re: &str, text: &str) {
     let result = match_text(re, text);
-    assert_eq!(
-        result, true,
-        "Expected regex '{}' to match text '{}'",
-        re, text
-    );
+    assert!(result, "Expected regex '{}' to match text '{}'", re, text);
 }
 
 fn assert_no_match(re: &str, text: &str) {
     let result = match_text(re, text);
-    assert_eq!(
-        result, false,
+    assert!(
+        !result,
         "Expected regex '{}' to not match text '{}'",
         re, text
     );
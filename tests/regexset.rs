@@ -0,0 +1,96 @@
+use fancy_regex::{Error, RegexSet};
+
+#[test]
+fn matches_easy_and_fancy() {
+    let set = regex_set(&[r"\d+", r"(\w+) \1", r"foo(?=bar)", r"x"]);
+    assert_eq!(matches(&set, "say it it 42"), vec![0, 1]);
+    assert_eq!(matches(&set, "foobar"), vec![2]);
+    assert_eq!(matches(&set, "foobaz x"), vec![3]);
+    assert_eq!(matches(&set, "-"), Vec::<usize>::new());
+}
+
+#[test]
+fn matches_details() {
+    let set = regex_set(&[r"a", r"(b)\1"]);
+    let result = set.matches("bb").unwrap();
+    assert!(result.matched_any());
+    assert!(!result.matched(0));
+    assert!(result.matched(1));
+    assert_eq!(result.len(), 2);
+
+    let result = set.matches("c").unwrap();
+    assert!(!result.matched_any());
+}
+
+#[test]
+fn is_match() {
+    let set = regex_set(&[r"\d", r"(?<=a)b"]);
+    assert!(set.is_match("1").unwrap());
+    assert!(set.is_match("ab").unwrap());
+    assert!(!set.is_match("b").unwrap());
+}
+
+#[test]
+fn required_literal_prefilter() {
+    // Matching this would exceed the backtrack limit, but the text doesn't contain `bc` so the
+    // regex doesn't have to be run at all.
+    let set = regex_set(&[r"(a|b|ab)*bc(?=d)"]);
+    let text = "ab".repeat(1000) + "ac";
+    assert!(!set.is_match(&text).unwrap());
+    assert!(!set.matches(&text).unwrap().matched_any());
+
+    let text = "ab".repeat(10) + "bcd";
+    assert_eq!(matches(&set, &text), vec![0]);
+}
+
+#[test]
+fn find_matches() {
+    let set = regex_set(&[r"o+", r"(\w)\1", r"(?<!f)o", r"z"]);
+    let found: Vec<_> = set
+        .find_matches("foo")
+        .unwrap()
+        .into_iter()
+        .map(|m| m.map(|m| (m.start(), m.end())))
+        .collect();
+    assert_eq!(found, vec![Some((1, 3)), Some((1, 3)), Some((2, 3)), None]);
+}
+
+#[test]
+fn empty_set() {
+    let set = RegexSet::new(Vec::<&str>::new()).unwrap();
+    assert!(set.is_empty());
+    assert!(!set.is_match("a").unwrap());
+    assert!(!set.matches("a").unwrap().matched_any());
+}
+
+#[test]
+fn patterns() {
+    let set = regex_set(&[r"a", r"(b)\1"]);
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.patterns(), vec![r"a", r"(b)\1"]);
+}
+
+#[test]
+fn invalid_pattern() {
+    let result = RegexSet::new([r"a", r"(b"]);
+    assert!(result.is_err());
+    match result.err() {
+        Some(Error::UnclosedOpenParen) => {}
+        _ => panic!("Expected Error::UnclosedOpenParen"),
+    }
+}
+
+fn regex_set(patterns: &[&str]) -> RegexSet {
+    let result = RegexSet::new(patterns);
+    assert!(
+        result.is_ok(),
+        "Expected regexes {:?} to be compiled successfully, got {:?}",
+        patterns,
+        result.err()
+    );
+    result.unwrap()
+}
+
+fn matches(set: &RegexSet, text: &str) -> Vec<usize> {
+    set.matches(text).unwrap().iter().collect()
+}
@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+
+use fancy_regex::{Captures, Error, NoExpand, RegexBuilder, Replacer};
+
+mod common;
+
+#[test]
+fn replace_wrap() {
+    assert_eq!(replace(r"(\d+)", "a 12 b 3", "<$1>"), "a <12> b 3");
+    assert_eq!(replace_all(r"(\d+)", "a 12 b 3", "<$1>"), "a <12> b <3>");
+}
+
+#[test]
+fn replace_fancy() {
+    assert_eq!(replace(r"(\w)\1", "aabbc", "$1"), "abbc");
+    assert_eq!(replace_all(r"(\w)\1", "aabbc", "$1"), "abc");
+    assert_eq!(replace_all(r"(?<=a)b", "abbab", "X"), "aXbaX");
+}
+
+#[test]
+fn replace_keep_out() {
+    assert_eq!(replace_all(r"(\w+)=\K\w+", "a=1 b=2", "$1"), "a=a b=b");
+    assert_eq!(replace_all(r"a\K", "aaa", "-"), "a-a-a-");
+}
+
+#[test]
+fn replacen() {
+    let regex = common::regex(r"(\w)\1");
+    assert_eq!(regex.replacen("aabbcc", 2, "-").unwrap(), "--cc");
+    assert_eq!(regex.replacen("aabbcc", 0, "-").unwrap(), "---");
+}
+
+#[test]
+fn replacen_stops_at_limit() {
+    // the search after the first match would exceed the backtrack limit
+    let regex = common::regex(r"(?i)x|(a|b|ab)*(?=c)");
+    let text = format!("x{}", "ab".repeat(28));
+    assert!(regex.replace_all(&text, "-").is_err());
+    assert_eq!(
+        regex.replacen(&text, 1, "-").unwrap(),
+        text.replacen('x', "-", 1)
+    );
+    assert_eq!(
+        regex.replacen(&text, 1, "$0-").unwrap(),
+        text.replacen('x', "x-", 1)
+    );
+}
+
+#[test]
+fn replace_no_match_borrows() {
+    let regex = common::regex(r"(\w)\1");
+    let result = regex.replace_all("abc", "$1").unwrap();
+    assert!(matches!(result, Cow::Borrowed("abc")));
+}
+
+#[test]
+fn replace_expansion() {
+    assert_eq!(replace_all(r"(a)(b)?", "ab a", "[$2$1]"), "[ba] [a]");
+    assert_eq!(replace_all(r"(a)(?=b)", "ab", "${1}1"), "a1b");
+    assert_eq!(replace_all(r"(a)(?=b)", "ab", "$1a"), "b");
+    assert_eq!(replace_all(r"(a)(?=b)", "ab", "$$1"), "$1b");
+    assert_eq!(replace_all(r"(a)(?=b)", "ab", "$"), "$b");
+    assert_eq!(replace_all(r"(a)(?=b)", "ab", "$9"), "b");
+    assert_eq!(replace_all(r"(a)(?=b)", "ab", "$ ${"), "$ ${b");
+}
+
+#[test]
+fn replace_named_expansion() {
+    assert_eq!(
+        replace_all(r"(?<first>\w)(?<second>\w)", "ab cd", "$second$first"),
+        "ba dc"
+    );
+    assert_eq!(replace_all(r"(?<a>\w)\1", "xx", "${a}_"), "x_");
+    assert_eq!(replace_all(r"(?<a>\w)\1", "xx", "$a_"), "");
+}
+
+#[test]
+fn replace_no_expand() {
+    let regex = common::regex(r"(a)(?=b)");
+    assert_eq!(regex.replace_all("ab", NoExpand("$1")).unwrap(), "$1b");
+}
+
+#[test]
+fn replace_closure() {
+    let regex = common::regex(r"(\w)\1");
+    let result = regex
+        .replace_all("aabbc", |caps: &Captures| {
+            caps.get(1).unwrap().as_str().to_uppercase()
+        })
+        .unwrap();
+    assert_eq!(result, "ABc");
+}
+
+#[test]
+fn replace_by_ref() {
+    let regex = common::regex(r"(\w)\1");
+    let mut count = 0;
+    let mut counter = |_: &Captures| {
+        count += 1;
+        count.to_string()
+    };
+    assert_eq!(regex.replace_all("aa bb", counter.by_ref()).unwrap(), "1 2");
+    assert_eq!(regex.replace_all("cc", counter.by_ref()).unwrap(), "3");
+}
+
+#[test]
+fn replace_empty_matches() {
+    assert_eq!(replace_all(r"a*(?=b|$)", "aab", "-"), "-b-");
+}
+
+#[test]
+fn replace_backtrack_limit() {
+    let regex = RegexBuilder::new(r"(x+x+)+(?=y)")
+        .backtrack_limit(1)
+        .build()
+        .unwrap();
+    let result = regex.replace_all("xxxxxxxxxxy", "");
+    assert_eq!(result.err(), Some(Error::BacktrackLimitExceeded));
+}
+
+fn replace(re: &str, text: &str, rep: &str) -> String {
+    let regex = common::regex(re);
+    regex.replace(text, rep).unwrap().into_owned()
+}
+
+fn replace_all(re: &str, text: &str, rep: &str) -> String {
+    let regex = common::regex(re);
+    regex.replace_all(text, rep).unwrap().into_owned()
+}
@@ -0,0 +1,55 @@
+use fancy_regex::{Error, RegexBuilder};
+
+mod common;
+
+#[test]
+fn split_wrap() {
+    assert_eq!(split(r",", "a,b,,c"), vec!["a", "b", "", "c"]);
+    assert_eq!(split(r"\s+", "  a b "), vec!["", "a", "b", ""]);
+    assert_eq!(split(r",", "abc"), vec!["abc"]);
+    assert_eq!(split(r",", ""), vec![""]);
+}
+
+#[test]
+fn split_fancy() {
+    assert_eq!(split(r"(?<!\\),", r"a,b\,c,d"), vec!["a", r"b\,c", "d"]);
+    assert_eq!(split(r"(\w)\1", "xaaybbz"), vec!["x", "y", "z"]);
+    assert_eq!(split(r"(\w)\1", "aa"), vec!["", ""]);
+}
+
+#[test]
+fn split_empty_matches() {
+    // Same results as the regex crate
+    assert_eq!(split(r"", "abc"), vec!["", "a", "b", "c", ""]);
+    assert_eq!(split(r"(?=b)", "abab"), vec!["a", "ba", "b"]);
+}
+
+#[test]
+fn splitn_limit() {
+    assert_eq!(splitn(r",", "a,b,c", 0), Vec::<&str>::new());
+    assert_eq!(splitn(r",", "a,b,c", 1), vec!["a,b,c"]);
+    assert_eq!(splitn(r",", "a,b,c", 2), vec!["a", "b,c"]);
+    assert_eq!(splitn(r",", "a,b,c", 5), vec!["a", "b", "c"]);
+    assert_eq!(splitn(r"(?<=b),", "a,b,c,b,d", 2), vec!["a,b", "c,b,d"]);
+}
+
+#[test]
+fn split_backtrack_limit() {
+    let regex = RegexBuilder::new(r"(x+x+)+(?=y)")
+        .backtrack_limit(1)
+        .build()
+        .unwrap();
+    let mut split = regex.split("xxxxxxxxxxy");
+    assert_eq!(split.next(), Some(Err(Error::BacktrackLimitExceeded)));
+    assert_eq!(split.next(), None);
+}
+
+fn split<'a>(re: &str, text: &'a str) -> Vec<&'a str> {
+    let regex = common::regex(re);
+    regex.split(text).map(|s| s.unwrap()).collect()
+}
+
+fn splitn<'a>(re: &str, text: &'a str, limit: usize) -> Vec<&'a str> {
+    let regex = common::regex(re);
+    regex.splitn(text, limit).map(|s| s.unwrap()).collect()
+}